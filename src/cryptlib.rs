@@ -1,5 +1,5 @@
 // use ndarray::{s, Array, Array1, Array3, ArrayView3};
-use rug::{ops::Pow, Float, Integer, Rational};
+use rug::{integer::IsPrime, integer::Order, ops::Pow, Float, Integer, Rational};
 use std::{
     fmt::Debug,
     ops::{AddAssign, Mul},
@@ -82,17 +82,18 @@ pub fn multiply_poly_zn(f: &[Integer], g: &[Integer], n: &Integer) -> Vec<Intege
     if debug {
         print!(" mult {:?}*{:?}", f, g);
     }
-    let mut prod = Vec::new();
-    for _ in 0..(degree(f) + degree(g) + 1) {
-        prod.push(Integer::from(0));
-    }
-
-    for i in 0..(degree(f) as usize + 1) {
-        for j in 0..(degree(g) as usize + 1) {
-            prod[i + j] += Integer::from(&f[i] * &g[j]);
-            if *n > 0 {
-                prod[i + j] %= n;
-            }
+    let f = &f[0..=(degree(f) as usize)];
+    let g = &g[0..=(degree(g) as usize)];
+    let mut prod = if *n > 0 {
+        let f_reduced: Vec<Integer> = f.iter().map(|x| Integer::from(x % n)).collect();
+        let g_reduced: Vec<Integer> = g.iter().map(|x| Integer::from(x % n)).collect();
+        multiply_poly_fast(&f_reduced, &g_reduced)
+    } else {
+        multiply_poly_fast(f, g)
+    };
+    if *n > 0 {
+        for val in prod.iter_mut() {
+            *val %= n;
         }
     }
     if debug {
@@ -101,6 +102,392 @@ pub fn multiply_poly_zn(f: &[Integer], g: &[Integer], n: &Integer) -> Vec<Intege
     return prod;
 }
 
+const KARATSUBA_THRESHOLD: usize = 16;
+const KRONECKER_THRESHOLD: usize = 32;
+
+/// Returns f * g over the integers, picking schoolbook, Karatsuba or Kronecker by size
+/// GMP multiplication makes Kronecker substitution faster than multiply_poly_ntt at every size
+/// measured (up to 2^20 coefficients of 4 to 256 bits), so the NTT is never picked here
+pub fn multiply_poly_fast(f: &[Integer], g: &[Integer]) -> Vec<Integer> {
+    if f.is_empty() || g.is_empty() {
+        return Vec::new();
+    }
+    let len = std::cmp::min(f.len(), g.len());
+    if len < KARATSUBA_THRESHOLD {
+        return multiply_poly_schoolbook(f, g);
+    }
+    if len >= KRONECKER_THRESHOLD {
+        return multiply_poly_kronecker(f, g);
+    }
+    return multiply_poly_karatsuba(f, g);
+}
+
+/// Karatsuba multiplication over the integers, falling back to schoolbook for small inputs
+pub fn multiply_poly_karatsuba(f: &[Integer], g: &[Integer]) -> Vec<Integer> {
+    if f.is_empty() || g.is_empty() {
+        return Vec::new();
+    }
+    if std::cmp::min(f.len(), g.len()) < KARATSUBA_THRESHOLD {
+        return multiply_poly_schoolbook(f, g);
+    }
+    let (f, g) = if f.len() >= g.len() { (f, g) } else { (g, f) };
+    let m = f.len() / 2;
+    let mut prod: Vec<Integer> = (0..(f.len() + g.len() - 1))
+        .map(|_| Integer::new())
+        .collect();
+
+    if g.len() <= m {
+        // unbalanced: split only the longer operand
+        let low = multiply_poly_karatsuba(&f[..m], g);
+        let high = multiply_poly_karatsuba(&f[m..], g);
+        for (i, val) in low.into_iter().enumerate() {
+            prod[i] += val;
+        }
+        for (i, val) in high.into_iter().enumerate() {
+            prod[i + m] += val;
+        }
+        return prod;
+    }
+
+    let (f0, f1) = f.split_at(m);
+    let (g0, g1) = g.split_at(m);
+    let z0 = multiply_poly_karatsuba(f0, g0);
+    let z2 = multiply_poly_karatsuba(f1, g1);
+    let f_sum = add_poly(f0, f1);
+    let g_sum = add_poly(g0, g1);
+    let mut z1 = multiply_poly_karatsuba(&f_sum, &g_sum);
+    for (i, val) in z0.iter().enumerate() {
+        z1[i] -= val;
+    }
+    for (i, val) in z2.iter().enumerate() {
+        z1[i] -= val;
+    }
+
+    for (i, val) in z0.into_iter().enumerate() {
+        prod[i] += val;
+    }
+    for (i, val) in z1.into_iter().enumerate() {
+        if i + m < prod.len() {
+            prod[i + m] += val;
+        }
+    }
+    for (i, val) in z2.into_iter().enumerate() {
+        prod[i + 2 * m] += val;
+    }
+    return prod;
+}
+
+/// Schoolbook product of length f.len() + g.len() - 1, unlike multiply_poly it keeps zero leading terms
+fn multiply_poly_schoolbook(f: &[Integer], g: &[Integer]) -> Vec<Integer> {
+    let mut prod: Vec<Integer> = (0..(f.len() + g.len() - 1))
+        .map(|_| Integer::new())
+        .collect();
+    for (i, f_i) in f.iter().enumerate() {
+        for (j, g_j) in g.iter().enumerate() {
+            prod[i + j] += Integer::from(f_i * g_j);
+        }
+    }
+    return prod;
+}
+
+fn add_poly(f: &[Integer], g: &[Integer]) -> Vec<Integer> {
+    let mut sum: Vec<Integer> = (0..std::cmp::max(f.len(), g.len()))
+        .map(|_| Integer::new())
+        .collect();
+    for (i, val) in f.iter().enumerate() {
+        sum[i] += val;
+    }
+    for (i, val) in g.iter().enumerate() {
+        sum[i] += val;
+    }
+    return sum;
+}
+
+/// Kronecker substitution: packs both polynomials into one integer each and lets GMP multiply
+pub fn multiply_poly_kronecker(f: &[Integer], g: &[Integer]) -> Vec<Integer> {
+    if f.is_empty() || g.is_empty() {
+        return Vec::new();
+    }
+    let max_bits = |v: &[Integer]| v.iter().map(|x| x.significant_bits()).max().unwrap();
+    let len = std::cmp::min(f.len(), g.len()) as u32;
+    // product coefficients are below len * max|f| * max|g|, plus a sign bit
+    let coef_bits = max_bits(f) + max_bits(g) + (32 - len.leading_zeros()) + 2;
+    let limbs = coef_bits.div_ceil(64) as usize;
+
+    let packed_f = kronecker_pack(f, limbs);
+    let packed_g = kronecker_pack(g, limbs);
+    let packed_prod = packed_f * packed_g;
+    return kronecker_unpack(&packed_prod, limbs, f.len() + g.len() - 1);
+}
+
+/// Evaluates f at 2^(64 limbs), writing negative coefficients as borrows from the next slot
+fn kronecker_pack(f: &[Integer], limbs: usize) -> Integer {
+    let slot = Integer::from(1) << (64 * limbs as u32);
+    let mut digits: Vec<u64> = Vec::with_capacity(f.len() * limbs);
+    let mut borrow = false;
+    for coef in f {
+        let mut val = Integer::from(coef - borrow as u32);
+        borrow = val < 0;
+        if borrow {
+            val += &slot;
+        }
+        let mut val_digits = val.to_digits::<u64>(Order::Lsf);
+        val_digits.resize(limbs, 0);
+        digits.append(&mut val_digits);
+    }
+    let packed = Integer::from_digits(&digits, Order::Lsf);
+    if borrow {
+        return packed - (Integer::from(1) << (64 * (limbs * f.len()) as u32));
+    }
+    return packed;
+}
+
+/// Splits x back into len signed coefficients of 64 limbs bits each
+fn kronecker_unpack(x: &Integer, limbs: usize, len: usize) -> Vec<Integer> {
+    let negative = *x < 0;
+    let mut digits = Integer::from(x.abs_ref()).to_digits::<u64>(Order::Lsf);
+    digits.resize(limbs * len, 0);
+    let slot = Integer::from(1) << (64 * limbs as u32);
+    let half_slot = Integer::from(&slot >> 1);
+    let mut carry = false;
+    let mut result = Vec::with_capacity(len);
+    for chunk in digits.chunks(limbs).take(len) {
+        let mut val = Integer::from_digits(chunk, Order::Lsf) + carry as u32;
+        carry = val >= half_slot;
+        if carry {
+            val -= &slot;
+        }
+        result.push(if negative { -val } else { val });
+    }
+    return result;
+}
+
+/// Number of 62 bit NTT primes whose product exceeds twice every coefficient of f * g
+fn ntt_primes_needed(f: &[Integer], g: &[Integer]) -> usize {
+    let max_bits = |v: &[Integer]| v.iter().map(|x| x.significant_bits()).max().unwrap();
+    let len = std::cmp::min(f.len(), g.len()) as u32;
+    let coef_bits = max_bits(f) + max_bits(g) + (32 - len.leading_zeros()) + 2;
+    return coef_bits.div_ceil(61) as usize;
+}
+
+/// Multi-prime number theoretic transform multiplication, recombining coefficients with the CRT
+pub fn multiply_poly_ntt(f: &[Integer], g: &[Integer]) -> Vec<Integer> {
+    if f.is_empty() || g.is_empty() {
+        return Vec::new();
+    }
+    let out_len = f.len() + g.len() - 1;
+    let mut size = 1;
+    let mut log_size = 0;
+    while size < out_len {
+        size <<= 1;
+        log_size += 1;
+    }
+    let primes = ntt_primes(ntt_primes_needed(f, g), log_size);
+
+    let residues: Vec<Vec<u64>> = primes
+        .iter()
+        .map(|&(p, root)| {
+            let p_int = Integer::from(p);
+            let mont = Montgomery::new(p);
+            let reduce = |v: &[Integer]| -> Vec<u64> {
+                let mut out: Vec<u64> = v
+                    .iter()
+                    .map(|x| {
+                        let r = Integer::from(x % &p_int);
+                        let r = if r < 0 { r + &p_int } else { r };
+                        mont.to_mont(r.to_u64().unwrap())
+                    })
+                    .collect();
+                out.resize(size, 0);
+                out
+            };
+            let mut a = reduce(f);
+            let mut b = reduce(g);
+            // root has order 2^32, square it down to order size
+            let mut w = mont.to_mont(root);
+            for _ in log_size..32 {
+                w = mont.mul(w, w);
+            }
+            ntt(&mut a, w, &mont);
+            ntt(&mut b, w, &mont);
+            for (x, y) in a.iter_mut().zip(&b) {
+                *x = mont.mul(*x, *y);
+            }
+            ntt(&mut a, mont.pow(w, p - 2), &mont);
+            let size_inv = mont.pow(mont.to_mont(size as u64), p - 2);
+            a.truncate(out_len);
+            // multiplying by size^-1 and leaving Montgomery form in one step
+            a.iter()
+                .map(|x| mont.reduce(mont.mul(*x, size_inv) as u128))
+                .collect()
+        })
+        .collect();
+
+    // CRT with precomputed cofactors, then map into the symmetric range
+    let modulus: Integer = primes.iter().map(|&(p, _)| Integer::from(p)).product();
+    let half_modulus = Integer::from(&modulus >> 1);
+    let cofactors: Vec<Integer> = primes
+        .iter()
+        .map(|&(p, _)| {
+            let p_int = Integer::from(p);
+            let m_i = Integer::from(&modulus / &p_int);
+            let inv = find_inverse(&m_i, &p_int);
+            m_i * inv
+        })
+        .collect();
+    return (0..out_len)
+        .map(|i| {
+            let mut val = Integer::new();
+            for (residue, cofactor) in residues.iter().zip(&cofactors) {
+                val += Integer::from(cofactor * residue[i]);
+            }
+            val %= &modulus;
+            if val > half_modulus {
+                val -= &modulus;
+            }
+            val
+        })
+        .collect();
+}
+
+/// Returns count primes p = c * 2^32 + 1 below 2^62, each with a root of unity of order 2^32
+fn ntt_primes(count: usize, log_size: u32) -> Vec<(u64, u64)> {
+    assert!(log_size <= 32, "polynomial too large for NTT");
+    let mut primes = Vec::new();
+    let mut c: u64 = (1 << 30) - 1;
+    while primes.len() < count {
+        let p = (c << 32) + 1;
+        c -= 1;
+        if Integer::from(p).is_probably_prime(30) == IsPrime::No {
+            continue;
+        }
+        // distinct prime factors of p - 1 = c * 2^32
+        let mut factors = vec![2];
+        let mut rest = (p - 1) >> 32;
+        let mut q = 2;
+        while q * q <= rest {
+            if rest.is_multiple_of(q) {
+                if !factors.contains(&q) {
+                    factors.push(q);
+                }
+                rest /= q;
+            } else {
+                q += 1;
+            }
+        }
+        if rest > 1 && !factors.contains(&rest) {
+            factors.push(rest);
+        }
+        let generator = (2..p)
+            .find(|g| factors.iter().all(|q| pow_mod_u64(*g, (p - 1) / q, p) != 1))
+            .unwrap();
+        primes.push((p, pow_mod_u64(generator, (p - 1) >> 32, p)));
+    }
+    return primes;
+}
+
+fn mul_mod_u64(a: u64, b: u64, p: u64) -> u64 {
+    return ((a as u128 * b as u128) % p as u128) as u64;
+}
+
+fn pow_mod_u64(mut base: u64, mut e: u64, p: u64) -> u64 {
+    let mut result = 1;
+    while e > 0 {
+        if e & 1 == 1 {
+            result = mul_mod_u64(result, base, p);
+        }
+        base = mul_mod_u64(base, base, p);
+        e >>= 1;
+    }
+    return result;
+}
+
+/// Montgomery arithmetic mod an odd p < 2^62, with R = 2^64
+struct Montgomery {
+    p: u64,
+    /// -p^-1 mod 2^64
+    p_neg_inv: u64,
+    /// R^2 mod p
+    r_squared: u64,
+}
+
+impl Montgomery {
+    fn new(p: u64) -> Montgomery {
+        let mut inv: u64 = 1;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(p.wrapping_mul(inv)));
+        }
+        let r_mod_p = ((1u128 << 64) % p as u128) as u64;
+        return Montgomery {
+            p,
+            p_neg_inv: inv.wrapping_neg(),
+            r_squared: mul_mod_u64(r_mod_p, r_mod_p, p),
+        };
+    }
+
+    /// Returns t / R mod p
+    fn reduce(&self, t: u128) -> u64 {
+        let m = (t as u64).wrapping_mul(self.p_neg_inv);
+        let u = ((t + m as u128 * self.p as u128) >> 64) as u64;
+        return if u >= self.p { u - self.p } else { u };
+    }
+
+    fn mul(&self, a: u64, b: u64) -> u64 {
+        return self.reduce(a as u128 * b as u128);
+    }
+
+    fn to_mont(&self, a: u64) -> u64 {
+        return self.mul(a, self.r_squared);
+    }
+
+    fn pow(&self, mut base: u64, mut e: u64) -> u64 {
+        let mut result = self.to_mont(1);
+        while e > 0 {
+            if e & 1 == 1 {
+                result = self.mul(result, base);
+            }
+            base = self.mul(base, base);
+            e >>= 1;
+        }
+        return result;
+    }
+}
+
+/// In-place iterative radix-2 NTT on values in Montgomery form, w must have order a.len()
+fn ntt(a: &mut [u64], w: u64, mont: &Montgomery) {
+    let p = mont.p;
+    let n = a.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            a.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let w_len = mont.pow(w, (n / len) as u64);
+        let mut twiddles = vec![mont.to_mont(1); len / 2];
+        for k in 1..(len / 2) {
+            twiddles[k] = mont.mul(twiddles[k - 1], w_len);
+        }
+        for start in (0..n).step_by(len) {
+            for (k, w_k) in twiddles.iter().enumerate() {
+                let u = a[start + k];
+                let v = mont.mul(a[start + k + len / 2], *w_k);
+                a[start + k] = if u + v >= p { u + v - p } else { u + v };
+                a[start + k + len / 2] = if u >= v { u - v } else { u + p - v };
+            }
+        }
+        len <<= 1;
+    }
+}
+
 fn degree<T>(f: &[T]) -> i32
 where
     T: PartialEq<i32>,
@@ -158,18 +545,19 @@ pub fn divide_poly_zn(
     f: &Vec<Integer>,
     g: &Vec<Integer>,
     n: &Integer,
-) -> (Vec<Integer>, Vec<Integer>) {
+) -> Result<(Vec<Integer>, Vec<Integer>), Integer> {
     // println!(" div {:?}/{:?}", f, g);
-    assert!(degree(g) > 0, "divide by 0");
-    let g = &g[0..=(degree(g) as usize)];
+    // leading coefficients that are multiples of n are zero, not factors of n
+    let g = trim_zn(g, n);
+    assert!(!g.is_empty(), "divide by 0");
+    let g_inv = unit_inverse(&g[g.len() - 1], n)?;
     let mut q: Vec<Integer> = (0..f.len()).map(|_x| Integer::from(0)).collect();
-    let mut r = f.clone();
+    let mut r: Vec<Integer> = f.iter().map(|val| Integer::from(val % n)).collect();
 
-    while r.iter().any(|x| *x != 0) && degree(&r) >= degree(g) {
+    while r.iter().any(|x| *x != 0) && degree(&r) >= degree(&g) {
         let (r_lead, r_power) = lead(&r);
-        let (g_lead, g_power) = lead(&g);
-        let g_inv = find_inverse(&g_lead, n);
-        let t = r_lead * g_inv % n;
+        let g_power = g.len() - 1;
+        let t = r_lead * &g_inv % n;
 
         let t_power = r_power - g_power;
 
@@ -186,7 +574,7 @@ pub fn divide_poly_zn(
     }
 
     // println!(" result q:{:?}, \tr:{:?}", q, r);
-    return (q, r);
+    return Ok((q, r));
 }
 
 pub fn exp_poly(f: &Vec<Integer>, e: &Integer) -> Vec<Integer> {
@@ -200,12 +588,337 @@ pub fn exp_poly(f: &Vec<Integer>, e: &Integer) -> Vec<Integer> {
     }
     let mut result = f.clone();
 
-    for _ in 0..Integer::from(e - 1).to_i32().unwrap() {
-        result = multiply_poly(&result, f);
+    // left to right square and multiply, starting below the top bit of e
+    let f_trimmed = &f[0..=(degree(f) as usize)];
+    for i in (0..(e.significant_bits() - 1)).rev() {
+        let trimmed = &result[0..=(degree(&result) as usize)];
+        result = multiply_poly_fast(trimmed, trimmed);
+        if e.get_bit(i) {
+            result = multiply_poly_fast(&result[0..=(degree(&result) as usize)], f_trimmed);
+        }
     }
     return result;
 }
 
+/// Returns f^e mod n by square and multiply, with coefficients in [0, n)
+pub fn exp_poly_zn(f: &[Integer], e: &Integer, n: &Integer) -> Vec<Integer> {
+    let mut result = vec![Integer::from(1)];
+    for i in (0..e.significant_bits()).rev() {
+        result = multiply_poly_zn(&result, &result, n);
+        if e.get_bit(i) {
+            result = multiply_poly_zn(&result, f, n);
+        }
+    }
+    return normalize_poly_zn(result, n);
+}
+
+/// Returns f^e mod (g, n) by square and multiply, for exponents far beyond the degree of g
+/// The leading coefficient of g must be a unit mod n
+pub fn powmod(f: &[Integer], e: &Integer, g: &[Integer], n: &Integer) -> Vec<Integer> {
+    assert!(g.iter().any(|x| *x != 0), "divide by 0");
+    let g = &g[0..=(degree(g) as usize)];
+    if g.len() == 1 {
+        return vec![Integer::from(0)];
+    }
+    let inverse = reversed_inverse(g, g.len() - 1, n);
+    // f may be longer than the inverse covers, so reduce it with its own
+    let base = rem_poly_zn(f, g, n);
+    let mut result = vec![Integer::from(1)];
+    for i in (0..e.significant_bits()).rev() {
+        let square = multiply_poly_zn(&result, &result, n);
+        result = rem_poly_with_inverse(&square, g, &inverse, n);
+        if e.get_bit(i) {
+            let product = multiply_poly_zn(&result, &base, n);
+            result = rem_poly_with_inverse(&product, g, &inverse, n);
+        }
+    }
+    return result;
+}
+
+/// Returns f(h) mod (g, n) with Brent-Kung baby steps h^i and giant steps h^k
+/// The leading coefficient of g must be a unit mod n
+pub fn compose_mod(f: &[Integer], h: &[Integer], g: &[Integer], n: &Integer) -> Vec<Integer> {
+    assert!(g.iter().any(|x| *x != 0), "divide by 0");
+    let g = &g[0..=(degree(g) as usize)];
+    if g.len() == 1 || f.is_empty() {
+        return vec![Integer::from(0)];
+    }
+    let f = &f[0..=(degree(f) as usize)];
+    let inverse = reversed_inverse(g, g.len() - 1, n);
+    let h = rem_poly_zn(h, g, n);
+
+    let k = (f.len() as f64).sqrt().ceil() as usize;
+    let mut h_powers = vec![vec![Integer::from(1)]];
+    for i in 1..=k {
+        let product = multiply_poly_zn(&h_powers[i - 1], &h, n);
+        h_powers.push(rem_poly_with_inverse(&product, g, &inverse, n));
+    }
+    let giant = h_powers.pop().unwrap();
+
+    // Horner in h^k over blocks of k coefficients, each block a combination of baby steps
+    let mut result = vec![Integer::from(0)];
+    for block in f.chunks(k).rev() {
+        let product = multiply_poly_zn(&result, &giant, n);
+        result = rem_poly_with_inverse(&product, g, &inverse, n);
+        for (coef, h_power) in block.iter().zip(&h_powers) {
+            if result.len() < h_power.len() {
+                result.resize(h_power.len(), Integer::from(0));
+            }
+            for (val, h_val) in result.iter_mut().zip(h_power) {
+                *val += Integer::from(coef * h_val);
+                *val %= n;
+            }
+        }
+    }
+    return normalize_poly_zn(result, n);
+}
+
+/// Returns f mod (g, n), the leading coefficient of g must be a unit mod n
+pub fn rem_poly_zn(f: &[Integer], g: &[Integer], n: &Integer) -> Vec<Integer> {
+    assert!(g.iter().any(|x| *x != 0), "divide by 0");
+    let g = &g[0..=(degree(g) as usize)];
+    if g.len() == 1 || f.is_empty() {
+        return vec![Integer::from(0)];
+    }
+    let f_degree = degree(f) as usize;
+    if f_degree < g.len() - 1 {
+        return normalize_poly_zn(f[0..=f_degree].to_vec(), n);
+    }
+    let inverse = reversed_inverse(g, f_degree + 2 - g.len(), n);
+    return rem_poly_with_inverse(f, g, &inverse, n);
+}
+
+/// Computes rev(g)^-1 mod (x^k, n) by Newton iteration, for division without long division
+fn reversed_inverse(g: &[Integer], k: usize, n: &Integer) -> Vec<Integer> {
+    let reversed: Vec<Integer> = g.iter().rev().cloned().collect();
+    let mut inverse = vec![find_inverse(&reversed[0], n)];
+    let mut precision = 1;
+    while precision < k {
+        precision = std::cmp::min(2 * precision, k);
+        // h = h (2 - rev(g) h) mod x^precision
+        let truncated = &reversed[..std::cmp::min(precision, reversed.len())];
+        let mut error = multiply_poly_zn(truncated, &inverse, n);
+        error.truncate(precision);
+        for val in error.iter_mut() {
+            *val = Integer::from(-&*val);
+        }
+        error[0] += 2;
+        inverse = multiply_poly_zn(&inverse, &error, n);
+        inverse.truncate(precision);
+    }
+    // keep zero high terms, the division needs the full length
+    inverse.resize(k, Integer::from(0));
+    for val in inverse.iter_mut() {
+        *val %= n;
+        if *val < 0 {
+            *val += n;
+        }
+    }
+    return inverse;
+}
+
+/// Remainder of f by g using the precomputed inverse of rev(g), valid while deg f - deg g < its length
+fn rem_poly_with_inverse(
+    f: &[Integer],
+    g: &[Integer],
+    inverse: &[Integer],
+    n: &Integer,
+) -> Vec<Integer> {
+    if f.is_empty() {
+        return vec![Integer::from(0)];
+    }
+    let f = &f[0..=(degree(f) as usize)];
+    let g_degree = g.len() - 1;
+    if f.len() <= g_degree {
+        return normalize_poly_zn(f.to_vec(), n);
+    }
+    let q_len = f.len() - g_degree;
+    assert!(
+        q_len <= inverse.len(),
+        "inverse too short for this division"
+    );
+    let f_reversed: Vec<Integer> = f.iter().rev().take(q_len).cloned().collect();
+    let mut q_reversed = multiply_poly_zn(&f_reversed, &inverse[..q_len], n);
+    q_reversed.resize(q_len, Integer::from(0));
+    let q: Vec<Integer> = q_reversed.into_iter().rev().collect();
+
+    let qg = multiply_poly_zn(&q, g, n);
+    let mut r: Vec<Integer> = f[..g_degree].to_vec();
+    for (val, qg_val) in r.iter_mut().zip(qg) {
+        *val -= qg_val;
+    }
+    return normalize_poly_zn(r, n);
+}
+
+/// Reduces coefficients into [0, n) and drops zero leading terms, keeping at least one term
+fn normalize_poly_zn(mut f: Vec<Integer>, n: &Integer) -> Vec<Integer> {
+    for val in f.iter_mut() {
+        *val %= n;
+        if *val < 0 {
+            *val += n;
+        }
+    }
+    while f.len() > 1 && f[f.len() - 1] == 0 {
+        f.pop();
+    }
+    if f.is_empty() {
+        f.push(Integer::from(0));
+    }
+    return f;
+}
+
+/// Evaluates f at every point by a remainder tree over the subproduct tree of the points
+/// Works mod n when n > 0 and over the integers otherwise, like eval_poly
+pub fn eval_poly_multi(f: &[Integer], points: &[Integer], n: &Integer) -> Vec<Integer> {
+    if points.is_empty() {
+        return Vec::new();
+    }
+    let tree = subproduct_tree(points, n);
+    return remainder_tree(f, &tree, n);
+}
+
+/// Returns the polynomial of degree < points.len() through (points[i], values[i]) mod n, by
+/// Lagrange interpolation over the subproduct tree
+/// Differences of points that are not units mod n reveal a factor of n instead
+pub fn interpolate_zn(
+    points: &[Integer],
+    values: &[Integer],
+    n: &Integer,
+) -> Result<Vec<Integer>, Integer> {
+    assert!(points.len() == values.len(), "need one value per point");
+    if points.is_empty() {
+        return Ok(vec![Integer::from(0)]);
+    }
+    let tree = subproduct_tree(points, n);
+    let root = &tree[tree.len() - 1][0];
+    let root_derivative: Vec<Integer> = (1..root.len())
+        .map(|i| Integer::from(&root[i] * i as u64) % n)
+        .collect();
+    let denominators = remainder_tree(&root_derivative, &tree, n);
+
+    // Lagrange weights at the leaves, then f = f_left M_right + f_right M_left up the tree
+    let mut combined = Vec::new();
+    for (value, denominator) in values.iter().zip(&denominators) {
+        assert!(
+            Integer::from(denominator % n) != 0,
+            "points must be distinct mod n"
+        );
+        let inverse = unit_inverse(denominator, n)?;
+        combined.push(vec![value * inverse % n]);
+    }
+    for level in &tree[..tree.len() - 1] {
+        let mut next = Vec::new();
+        for (i, pair) in combined.chunks(2).enumerate() {
+            if pair.len() == 2 {
+                let left = multiply_poly_zn(&pair[0], &level[2 * i + 1], n);
+                let right = multiply_poly_zn(&pair[1], &level[2 * i], n);
+                next.push(normalize_poly_zn(add_poly(&left, &right), n));
+            } else {
+                next.push(pair[0].clone());
+            }
+        }
+        combined = next;
+    }
+    return Ok(normalize_poly_zn(combined.pop().unwrap(), n));
+}
+
+/// Returns the polynomial of degree < points.len() through (points[i], values[i]) over Q, by
+/// Newton's divided differences
+pub fn interpolate_q(points: &[Rational], values: &[Rational]) -> Vec<Rational> {
+    assert!(points.len() == values.len(), "need one value per point");
+    let mut differences = values.to_vec();
+    for j in 1..points.len() {
+        for i in (j..points.len()).rev() {
+            let numerator = Rational::from(&differences[i] - &differences[i - 1]);
+            differences[i] = numerator / Rational::from(&points[i] - &points[i - j]);
+        }
+    }
+
+    // expand the Newton form c_0 + (x - x_0)(c_1 + (x - x_1)(c_2 + ...)) from the inside out
+    let mut result = vec![Rational::from(0)];
+    for (point, difference) in points.iter().zip(&differences).rev() {
+        let mut shifted = vec![Rational::from(0)];
+        shifted.extend(result.iter().cloned());
+        for (val, next) in shifted.iter_mut().zip(&result) {
+            *val -= Rational::from(next * point);
+        }
+        shifted[0] += difference;
+        result = shifted;
+    }
+    while result.len() > 1 && result[result.len() - 1] == 0 {
+        result.pop();
+    }
+    return result;
+}
+
+/// Levels of products of x - points[i], from the linear factors up to their full product
+fn subproduct_tree(points: &[Integer], n: &Integer) -> Vec<Vec<Vec<Integer>>> {
+    let leaves = points
+        .iter()
+        .map(|point| reduce_poly(vec![Integer::from(-point), Integer::from(1)], n))
+        .collect();
+    let mut tree: Vec<Vec<Vec<Integer>>> = vec![leaves];
+    while tree[tree.len() - 1].len() > 1 {
+        let next = tree[tree.len() - 1]
+            .chunks(2)
+            .map(|pair| {
+                if pair.len() == 2 {
+                    reduce_poly(multiply_poly_zn(&pair[0], &pair[1], n), n)
+                } else {
+                    pair[0].clone()
+                }
+            })
+            .collect();
+        tree.push(next);
+    }
+    return tree;
+}
+
+/// Reduces f down the subproduct tree, returning f(points[i]) for each leaf
+fn remainder_tree(f: &[Integer], tree: &[Vec<Vec<Integer>>], n: &Integer) -> Vec<Integer> {
+    let mut remainders = vec![rem_monic(f, &tree[tree.len() - 1][0], n)];
+    for level in tree[..tree.len() - 1].iter().rev() {
+        remainders = level
+            .iter()
+            .enumerate()
+            .map(|(i, node)| rem_monic(&remainders[i / 2], node, n))
+            .collect();
+    }
+    return remainders.into_iter().map(|r| r[0].clone()).collect();
+}
+
+/// Remainder by a monic polynomial, mod n when n > 0 and over the integers otherwise
+fn rem_monic(f: &[Integer], g: &[Integer], n: &Integer) -> Vec<Integer> {
+    if *n > 0 {
+        return rem_poly_zn(f, g, n);
+    }
+    if f.is_empty() {
+        return vec![Integer::from(0)];
+    }
+    let mut r = f[0..=(degree(f) as usize)].to_vec();
+    let g_degree = g.len() - 1;
+    while r.len() > g_degree && r.len() > 1 {
+        let t = r.pop().unwrap();
+        let shift = r.len() - g_degree;
+        for (i, val) in g[..g_degree].iter().enumerate() {
+            r[shift + i] -= Integer::from(val * &t);
+        }
+    }
+    if r.is_empty() {
+        r.push(Integer::from(0));
+    }
+    return r;
+}
+
+/// normalize_poly_zn when n > 0, otherwise just drops zero leading terms
+fn reduce_poly(f: Vec<Integer>, n: &Integer) -> Vec<Integer> {
+    if *n > 0 {
+        return normalize_poly_zn(f, n);
+    }
+    let len = degree(&f) as usize + 1;
+    return f[..len].to_vec();
+}
+
 pub fn determinant(
     matrix: &Vec<Vec<Vec<Integer>>>,
     cols: &Vec<usize>,
@@ -327,6 +1040,177 @@ pub fn resultant(f: &Vec<Vec<Integer>>, g: &Vec<Vec<Integer>>, n: &Integer) -> V
     return determinant;
 }
 
+/// Resultant in x of f and g as a polynomial in y, with f[i][j] the coefficient of x^i y^j
+/// Evaluates at enough values of y to fix its degree and interpolates, mod n when n > 0 and
+/// over the integers otherwise, instead of expanding the Sylvester determinant
+pub fn resultant_interpolate(
+    f: &Vec<Vec<Integer>>,
+    g: &Vec<Vec<Integer>>,
+    n: &Integer,
+) -> Result<Vec<Integer>, Integer> {
+    let f_degree = f.len() - 1;
+    let g_degree = g.len() - 1;
+    let y_degree =
+        |h: &Vec<Vec<Integer>>| h.iter().map(|coef| degree(coef) as usize).max().unwrap();
+    let bound = f_degree * y_degree(g) + g_degree * y_degree(f);
+    let points: Vec<Integer> = (0..=bound).map(Integer::from).collect();
+
+    // every x coefficient evaluated at all the points at once
+    let f_values: Vec<Vec<Integer>> = f
+        .iter()
+        .map(|coef| eval_poly_multi(coef, &points, n))
+        .collect();
+    let g_values: Vec<Vec<Integer>> = g
+        .iter()
+        .map(|coef| eval_poly_multi(coef, &points, n))
+        .collect();
+    let at_point = |values: &Vec<Vec<Integer>>, k: usize| -> Vec<Integer> {
+        values
+            .iter()
+            .map(|coef_values| coef_values[k].clone())
+            .collect()
+    };
+
+    if *n > 0 {
+        let mut resultants = Vec::new();
+        for k in 0..points.len() {
+            let f_k = trim_zn(&at_point(&f_values, k), n);
+            let g_k = trim_zn(&at_point(&g_values, k), n);
+            resultants.push(resultant_zn(f_k, g_k, f_degree, g_degree, n)?);
+        }
+        return interpolate_zn(&points, &resultants, n);
+    }
+
+    let to_rational = |values: Vec<Integer>| -> Vec<Rational> {
+        let mut values: Vec<Rational> = values.into_iter().map(Rational::from).collect();
+        while values.last().is_some_and(|val| *val == 0) {
+            values.pop();
+        }
+        values
+    };
+    let resultants: Vec<Rational> = (0..points.len())
+        .map(|k| {
+            let f_k = to_rational(at_point(&f_values, k));
+            let g_k = to_rational(at_point(&g_values, k));
+            resultant_q(f_k, g_k, f_degree, g_degree)
+        })
+        .collect();
+    let rational_points: Vec<Rational> = points.into_iter().map(Rational::from).collect();
+    let interpolated = interpolate_q(&rational_points, &resultants);
+    return Ok(interpolated
+        .into_iter()
+        .map(|coef| coef.numer().clone())
+        .collect());
+}
+
+/// Resultant mod n of a and b taken with formal degrees a_degree and b_degree, by the
+/// Euclidean algorithm, or a factor of n met on a leading coefficient
+fn resultant_zn(
+    mut a: Vec<Integer>,
+    mut b: Vec<Integer>,
+    a_degree: usize,
+    b_degree: usize,
+    n: &Integer,
+) -> Result<Integer, Integer> {
+    if a.is_empty() || b.is_empty() || (a.len() <= a_degree && b.len() <= b_degree) {
+        return Ok(Integer::from(0));
+    }
+    // a leading coefficient vanishing at this point drops a row from the Sylvester matrix
+    let mut result = Integer::from(1);
+    let a_drop = (a_degree + 1 - a.len()) as u32;
+    let b_drop = (b_degree + 1 - b.len()) as u32;
+    result *= fast_power(&b[b.len() - 1], &Integer::from(a_drop), n);
+    if (a_drop as usize * b_degree) % 2 == 1 {
+        result = -result;
+    }
+    result *= fast_power(&a[a.len() - 1], &Integer::from(b_drop), n);
+
+    loop {
+        let a_actual = a.len() - 1;
+        let b_actual = b.len() - 1;
+        if b_actual == 0 {
+            result *= fast_power(&b[0], &Integer::from(a_actual), n);
+            break;
+        }
+        let (_q, r) = divrem_zn(&a, &b, n)?;
+        if r.is_empty() {
+            return Ok(Integer::from(0));
+        }
+        // Res(a, b) = (-1)^(deg a deg b) lc(b)^(deg a - deg r) Res(b, r)
+        if (a_actual * b_actual) % 2 == 1 {
+            result = -result;
+        }
+        result *= fast_power(&b[b_actual], &Integer::from(a_actual + 1 - r.len()), n);
+        result %= n;
+        a = b;
+        b = r;
+    }
+    return Ok(((result % n) + n) % n);
+}
+
+/// Resultant over the integers of f and g, with formal degrees f.len() - 1 and g.len() - 1
+/// An empty f or g is the zero polynomial, whose resultant with anything is 0
+pub fn resultant_univariate(f: &[Integer], g: &[Integer]) -> Integer {
+    if f.is_empty() || g.is_empty() {
+        return Integer::from(0);
+    }
+    let to_rational = |h: &[Integer]| -> Vec<Rational> {
+        let len = h.iter().rposition(|val| *val != 0).map_or(0, |i| i + 1);
+        h[..len].iter().map(Rational::from).collect()
+    };
+    let result = resultant_q(to_rational(f), to_rational(g), f.len() - 1, g.len() - 1);
+    return result.numer().clone();
+}
+
+/// Resultant over Q of a and b taken with formal degrees a_degree and b_degree
+fn resultant_q(
+    mut a: Vec<Rational>,
+    mut b: Vec<Rational>,
+    a_degree: usize,
+    b_degree: usize,
+) -> Rational {
+    if a.is_empty() || b.is_empty() || (a.len() <= a_degree && b.len() <= b_degree) {
+        return Rational::from(0);
+    }
+    let mut result = Rational::from(1);
+    let a_drop = (a_degree + 1 - a.len()) as i32;
+    let b_drop = (b_degree + 1 - b.len()) as i32;
+    result *= b[b.len() - 1].clone().pow(a_drop);
+    if (a_drop as usize * b_degree) % 2 == 1 {
+        result = -result;
+    }
+    result *= a[a.len() - 1].clone().pow(b_drop);
+
+    loop {
+        let a_actual = a.len() - 1;
+        let b_actual = b.len() - 1;
+        if b_actual == 0 {
+            result *= b[0].clone().pow(a_actual as i32);
+            return result;
+        }
+        // a mod b by long division
+        let lead_inv = Rational::from(1) / &b[b_actual];
+        while a.len() > b_actual {
+            let t = a.pop().unwrap() * &lead_inv;
+            let shift = a.len() - b_actual;
+            for (i, val) in b[..b_actual].iter().enumerate() {
+                a[shift + i] -= Rational::from(val * &t);
+            }
+        }
+        while a.last().is_some_and(|val| *val == 0) {
+            a.pop();
+        }
+        if a.is_empty() {
+            return Rational::from(0);
+        }
+        if (a_actual * b_actual) % 2 == 1 {
+            result = -result;
+        }
+        result *= b[b_actual].clone().pow((a_actual - (a.len() - 1)) as i32);
+        std::mem::swap(&mut a, &mut b);
+    }
+}
+
 pub fn gcd(a: &Integer, b: &Integer) -> Integer {
     let (r, _s, _t) = extended_euclidean(a, b);
     // println!("gcd {}", r);
@@ -345,6 +1229,22 @@ pub fn find_inverse(e: &Integer, n: &Integer) -> Integer {
     }
 }
 
+/// Returns the inverse of e mod n, or the factor gcd(e, n) of n when e is not a unit
+/// e must be nonzero mod n, as gcd(0, n) = n says nothing about n
+pub fn unit_inverse(e: &Integer, n: &Integer) -> Result<Integer, Integer> {
+    let e = ((e.clone() % n) + n) % n;
+    assert!(e != 0, "0 has no inverse mod n");
+    let (r, _s, t) = extended_euclidean(n, &e);
+    if r != 1 {
+        return Err(r);
+    }
+    if t < 0 {
+        return Ok(t + n);
+    } else {
+        return Ok(t);
+    }
+}
+
 pub fn bezout(a: &Integer, b: &Integer) -> (Integer, Integer) {
     if a > b {
         let (_, s, t) = extended_euclidean(a, b);
@@ -356,6 +1256,35 @@ pub fn bezout(a: &Integer, b: &Integer) -> (Integer, Integer) {
 }
 
 pub fn extended_euclidean(a: &Integer, b: &Integer) -> (Integer, Integer, Integer) {
+    let (result, _qs) = extended_euclidean_quotients(a, b);
+    return result;
+}
+
+/// Partial quotients [a_0; a_1, ..., a_k] of the continued fraction of |a| / |b|
+pub fn continued_fraction(a: &Integer, b: &Integer) -> Vec<Integer> {
+    let (_result, qs) = extended_euclidean_quotients(a, b);
+    return qs;
+}
+
+/// Convergents h_i / k_i of [a_0; a_1, ...] as (h_i, k_i), from h_i = a_i h_i-1 + h_i-2
+pub fn convergents(quotients: &[Integer]) -> Vec<(Integer, Integer)> {
+    let mut result = Vec::new();
+    let (mut h_prev, mut h) = (Integer::from(0), Integer::from(1));
+    let (mut k_prev, mut k) = (Integer::from(1), Integer::from(0));
+    for a in quotients {
+        let h_next = Integer::from(a * &h) + &h_prev;
+        let k_next = Integer::from(a * &k) + &k_prev;
+        h_prev = std::mem::replace(&mut h, h_next);
+        k_prev = std::mem::replace(&mut k, k_next);
+        result.push((h.clone(), k.clone()));
+    }
+    return result;
+}
+
+fn extended_euclidean_quotients(
+    a: &Integer,
+    b: &Integer,
+) -> ((Integer, Integer, Integer), Vec<Integer>) {
     let a_negative = *a < 0;
     let b_negative = *b < 0;
     let a = Integer::from(a).abs();
@@ -407,14 +1336,18 @@ pub fn extended_euclidean(a: &Integer, b: &Integer) -> (Integer, Integer, Intege
     } else {
         ts.pop().unwrap()
     };
-    return (rs.pop().unwrap(), result_s, result_t);
+    return ((rs.pop().unwrap(), result_s, result_t), qs);
 }
 
+/// (g, s, t) with s a + t b = g for polynomials mod n
+type PolyBezout = (Vec<Integer>, Vec<Integer>, Vec<Integer>);
+
+/// Returns (r, s, t) with s a + t b = r = gcd(a, b) mod n, not normalized to be monic
 pub fn poly_extended_euclidean_zn(
     a: &Vec<Integer>,
     b: &Vec<Integer>,
     n: &Integer,
-) -> (Vec<Integer>, Vec<Integer>, Vec<Integer>) {
+) -> Result<PolyBezout, Integer> {
     let debug = false;
 
     let a = a.clone();
@@ -448,12 +1381,12 @@ pub fn poly_extended_euclidean_zn(
 
     let mut step = 1;
     while rs.last().unwrap().iter().any(|x| *x != 0) {
-        let (new_q, new_r) = divide_poly_zn(&rs[step - 1], &rs[step], n);
+        let (new_q, new_r) = divide_poly_zn(&rs[step - 1], &rs[step], n)?;
 
         let q_times_s = multiply_poly_zn(&new_q, &ss[step], n);
         let mut new_s = ss[step - 1].clone();
-        for _ in 0..(q_times_s.len() - new_s.len()) {
-            new_s.push(Integer::from(0));
+        if new_s.len() < q_times_s.len() {
+            new_s.resize(q_times_s.len(), Integer::from(0));
         }
         for (elem_s, elem_q) in new_s.iter_mut().zip(q_times_s) {
             *elem_s -= elem_q;
@@ -462,8 +1395,8 @@ pub fn poly_extended_euclidean_zn(
 
         let q_times_t = multiply_poly_zn(&new_q, &ts[step], &n);
         let mut new_t = ts[step - 1].clone();
-        for _ in 0..(q_times_t.len() - new_t.len()) {
-            new_t.push(Integer::from(0));
+        if new_t.len() < q_times_t.len() {
+            new_t.resize(q_times_t.len(), Integer::from(0));
         }
         for (elem_t, elem_q) in new_t.iter_mut().zip(q_times_t) {
             *elem_t -= elem_q;
@@ -487,7 +1420,259 @@ pub fn poly_extended_euclidean_zn(
     rs.pop();
     ss.pop();
     ts.pop();
-    return (rs.pop().unwrap(), ss.pop().unwrap(), ts.pop().unwrap());
+    return Ok((rs.pop().unwrap(), ss.pop().unwrap(), ts.pop().unwrap()));
+}
+
+/// Below this degree the half-GCD falls back to the classical Euclidean steps
+const HALF_GCD_THRESHOLD: usize = 64;
+/// Quotients shorter than this are computed by long division rather than Newton inversion
+const NEWTON_DIVISION_THRESHOLD: usize = 32;
+
+/// 2x2 matrix of polynomials mod n, mapping a pair of remainders to a later pair
+type PolyMatrix = [[Vec<Integer>; 2]; 2];
+
+/// Returns the monic gcd of a and b mod n by the half-GCD, without tracking cofactors
+pub fn poly_gcd_zn(a: &[Integer], b: &[Integer], n: &Integer) -> Result<Vec<Integer>, Integer> {
+    let mut a = trim_zn(a, n);
+    let mut b = trim_zn(b, n);
+    if a.len() < b.len() {
+        std::mem::swap(&mut a, &mut b);
+    }
+    while !b.is_empty() {
+        if a.len() > b.len() && b.len() > HALF_GCD_THRESHOLD {
+            // jump to a pair of about half the degree, the matrix itself is dropped
+            let matrix = half_gcd(&a, &b, n)?;
+            let (c, d) = apply_matrix(&matrix, &a, &b, n);
+            a = c;
+            b = d;
+            if b.is_empty() {
+                break;
+            }
+        }
+        let (_q, r) = divrem_zn(&a, &b, n)?;
+        a = b;
+        b = r;
+    }
+    return Ok(monic_zn(a, n)?.0);
+}
+
+/// Returns (g, s, t) with s a + t b = g mod n and g the monic gcd, by the half-GCD
+pub fn poly_extended_gcd_zn(
+    a: &[Integer],
+    b: &[Integer],
+    n: &Integer,
+) -> Result<PolyBezout, Integer> {
+    let mut a = trim_zn(a, n);
+    let mut b = trim_zn(b, n);
+    let mut cofactors = identity_matrix();
+    if a.len() < b.len() {
+        std::mem::swap(&mut a, &mut b);
+        cofactors = [
+            [Vec::new(), vec![Integer::from(1)]],
+            [vec![Integer::from(1)], Vec::new()],
+        ];
+    }
+    while !b.is_empty() {
+        if a.len() > b.len() && b.len() > HALF_GCD_THRESHOLD {
+            let matrix = half_gcd(&a, &b, n)?;
+            let (c, d) = apply_matrix(&matrix, &a, &b, n);
+            cofactors = multiply_matrix(&matrix, &cofactors, n);
+            a = c;
+            b = d;
+            if b.is_empty() {
+                break;
+            }
+        }
+        let (q, r) = divrem_zn(&a, &b, n)?;
+        cofactors = euclid_step(&q, cofactors, n);
+        a = b;
+        b = r;
+    }
+    let [[s, t], _] = cofactors;
+    let (g, scale) = monic_zn(a, n)?;
+    let s = multiply_trimmed_zn(&s, std::slice::from_ref(&scale), n);
+    let t = multiply_trimmed_zn(&t, &[scale], n);
+    return Ok((g, normalize_poly_zn(s, n), normalize_poly_zn(t, n)));
+}
+
+/// Returns M with (c, d) = M (a, b) consecutive remainders of a and b, deg c >= ceil(deg a / 2) > deg d
+/// Requires deg a > deg b
+fn half_gcd(a: &[Integer], b: &[Integer], n: &Integer) -> Result<PolyMatrix, Integer> {
+    let m = a.len() / 2;
+    if b.len() <= m {
+        return Ok(identity_matrix());
+    }
+    if a.len() < HALF_GCD_THRESHOLD {
+        let mut matrix = identity_matrix();
+        let mut a = a.to_vec();
+        let mut b = b.to_vec();
+        while b.len() > m {
+            let (q, r) = divrem_zn(&a, &b, n)?;
+            matrix = euclid_step(&q, matrix, n);
+            a = b;
+            b = r;
+        }
+        return Ok(matrix);
+    }
+
+    // the quotients of the top halves agree with those of a and b for the first half of the degree drop
+    let matrix = half_gcd(&a[m..], &b[m..], n)?;
+    let (c, d) = apply_matrix(&matrix, a, b, n);
+    if d.len() <= m {
+        return Ok(matrix);
+    }
+    let (q, r) = divrem_zn(&c, &d, n)?;
+    let matrix = euclid_step(&q, matrix, n);
+    if r.len() <= m {
+        return Ok(matrix);
+    }
+    let k = 2 * m + 1 - d.len();
+    let second = half_gcd(&d[k..], &r[k..], n)?;
+    return Ok(multiply_matrix(&second, &matrix, n));
+}
+
+/// Returns (q, r) with a = q b + r mod n, both trimmed, or the factor of n shared with the lead of b
+fn divrem_zn(
+    a: &[Integer],
+    b: &[Integer],
+    n: &Integer,
+) -> Result<(Vec<Integer>, Vec<Integer>), Integer> {
+    let lead_inv = unit_inverse(&b[b.len() - 1], n)?;
+    if a.len() < b.len() {
+        return Ok((Vec::new(), a.to_vec()));
+    }
+    let q_len = a.len() + 1 - b.len();
+    let q = if q_len < NEWTON_DIVISION_THRESHOLD || b.len() < NEWTON_DIVISION_THRESHOLD {
+        let mut r = a.to_vec();
+        let mut q = vec![Integer::from(0); q_len];
+        for i in (0..q_len).rev() {
+            let t = Integer::from(&r[i + b.len() - 1] * &lead_inv) % n;
+            if t != 0 {
+                for (j, val) in b.iter().enumerate() {
+                    r[i + j] -= Integer::from(val * &t);
+                    r[i + j] %= n;
+                }
+            }
+            q[i] = t;
+        }
+        q
+    } else {
+        let inverse = reversed_inverse(b, q_len, n);
+        let a_reversed: Vec<Integer> = a.iter().rev().take(q_len).cloned().collect();
+        let mut q_reversed = multiply_poly_zn(&a_reversed, &inverse, n);
+        q_reversed.resize(q_len, Integer::from(0));
+        q_reversed.into_iter().rev().collect()
+    };
+    let q = trim_zn(&q, n);
+    let qb = multiply_trimmed_zn(&q, b, n);
+    let mut r = a[..b.len() - 1].to_vec();
+    for (val, qb_val) in r.iter_mut().zip(qb) {
+        *val -= qb_val;
+    }
+    return Ok((q, trim_zn(&r, n)));
+}
+
+/// Reduces coefficients into [0, n) and drops zero leading terms, the zero polynomial is empty
+fn trim_zn(f: &[Integer], n: &Integer) -> Vec<Integer> {
+    let mut f: Vec<Integer> = f
+        .iter()
+        .map(|val| {
+            let val = Integer::from(val % n);
+            if val < 0 {
+                val + n
+            } else {
+                val
+            }
+        })
+        .collect();
+    while f.last().is_some_and(|val| *val == 0) {
+        f.pop();
+    }
+    return f;
+}
+
+/// Returns f scaled to be monic and the scale used, the zero polynomial becomes [0]
+fn monic_zn(f: Vec<Integer>, n: &Integer) -> Result<(Vec<Integer>, Integer), Integer> {
+    if f.is_empty() {
+        return Ok((vec![Integer::from(0)], Integer::from(1)));
+    }
+    let scale = unit_inverse(&f[f.len() - 1], n)?;
+    let monic = multiply_trimmed_zn(&f, std::slice::from_ref(&scale), n);
+    return Ok((normalize_poly_zn(monic, n), scale));
+}
+
+/// multiply_poly_zn on trimmed polynomials, where the zero polynomial is empty
+fn multiply_trimmed_zn(f: &[Integer], g: &[Integer], n: &Integer) -> Vec<Integer> {
+    if f.is_empty() || g.is_empty() {
+        return Vec::new();
+    }
+    return trim_zn(&multiply_poly_zn(f, g, n), n);
+}
+
+fn add_trimmed_zn(f: Vec<Integer>, g: Vec<Integer>, n: &Integer) -> Vec<Integer> {
+    let (mut long, short) = if f.len() >= g.len() { (f, g) } else { (g, f) };
+    for (val, short_val) in long.iter_mut().zip(short) {
+        *val += short_val;
+    }
+    return trim_zn(&long, n);
+}
+
+fn identity_matrix() -> PolyMatrix {
+    return [
+        [vec![Integer::from(1)], Vec::new()],
+        [Vec::new(), vec![Integer::from(1)]],
+    ];
+}
+
+/// Returns (m00 a + m01 b, m10 a + m11 b)
+fn apply_matrix(
+    matrix: &PolyMatrix,
+    a: &[Integer],
+    b: &[Integer],
+    n: &Integer,
+) -> (Vec<Integer>, Vec<Integer>) {
+    let [[m00, m01], [m10, m11]] = matrix;
+    let c = add_trimmed_zn(
+        multiply_trimmed_zn(m00, a, n),
+        multiply_trimmed_zn(m01, b, n),
+        n,
+    );
+    let d = add_trimmed_zn(
+        multiply_trimmed_zn(m10, a, n),
+        multiply_trimmed_zn(m11, b, n),
+        n,
+    );
+    return (c, d);
+}
+
+fn multiply_matrix(x: &PolyMatrix, y: &PolyMatrix, n: &Integer) -> PolyMatrix {
+    let entry = |i: usize, j: usize| {
+        add_trimmed_zn(
+            multiply_trimmed_zn(&x[i][0], &y[0][j], n),
+            multiply_trimmed_zn(&x[i][1], &y[1][j], n),
+            n,
+        )
+    };
+    return [[entry(0, 0), entry(0, 1)], [entry(1, 0), entry(1, 1)]];
+}
+
+/// Returns [[0, 1], [1, -q]] M, one step of the Euclidean algorithm
+fn euclid_step(q: &[Integer], matrix: PolyMatrix, n: &Integer) -> PolyMatrix {
+    let [row0, row1] = matrix;
+    let negated_q: Vec<Integer> = q.iter().map(|val| Integer::from(n - val)).collect();
+    let new_row1 = [
+        add_trimmed_zn(
+            row0[0].clone(),
+            multiply_trimmed_zn(&negated_q, &row1[0], n),
+            n,
+        ),
+        add_trimmed_zn(
+            row0[1].clone(),
+            multiply_trimmed_zn(&negated_q, &row1[1], n),
+            n,
+        ),
+    ];
+    return [row1, new_row1];
 }
 
 /// computes x^e mod n
@@ -857,6 +2042,7 @@ pub fn coppersmith(f: &Vec<Integer>, n: &Integer, m: u32, epsilon_denom: u32) ->
         }
     }
 
+    let mut candidates = Vec::new();
     for reduced_poly in &reduced_basis[0..2] {
         let search_range = 10;
         let guesses = approximate_zero(reduced_poly, &x_powers[1]);
@@ -866,106 +2052,140 @@ pub fn coppersmith(f: &Vec<Integer>, n: &Integer, m: u32, epsilon_denom: u32) ->
             // }
 
             for i in -search_range..=search_range {
-                let x = Integer::from(&guess_x + i);
-                let f_of_x = eval_poly(&x, f, n);
-                if f_of_x == 0 {
-                    return Some(x);
-                }
+                candidates.push(Integer::from(&guess_x + i));
             }
         }
     }
+    // check every candidate with one multipoint evaluation
+    let f_of_candidates = eval_poly_multi(f, &candidates, n);
+    for (x, f_of_x) in candidates.into_iter().zip(f_of_candidates) {
+        if f_of_x == 0 {
+            return Some(x);
+        }
+    }
     return None;
 }
 
+/// LLL with δ = 5/6, returning the reduced basis and the index of its shortest vector
+/// Integral version (Cohen, Algorithm 2.6.7): Gram-Schmidt is kept as the integers
+/// d_i = |b*_1|^2 ... |b*_i|^2 and λ_i,j = d_j μ_i,j, so each step updates O(n) entries exactly
+/// instead of recomputing rational projections, while making the same reductions and swaps
+/// Vectors that are not linearly independent are handed to mlll, so the basis can get shorter
 pub fn lll(basis_integer: &Vec<Vec<Integer>>) -> (Vec<Vec<Integer>>, usize) {
     let debug = false;
 
-    let n = basis_integer[0].len() - 1;
-    let delta = Rational::from((5, 6));
+    let n = basis_integer.len();
+    if n == 0 {
+        return (Vec::new(), 0);
+    }
+    let mut basis = basis_integer.clone();
 
-    let mut basis: Vec<Vec<Rational>> = basis_integer
+    let min_norm = basis
         .iter()
-        .map(|vec| vec.iter().map(|x| Rational::from(x)).collect())
-        .collect();
-
-    let min_norm = basis.iter().map(|v| l2_norm_squared(v)).min().unwrap();
+        .map(|v| inner_product_integer(v, v))
+        .min()
+        .unwrap();
 
     if debug {
-        println!("rational basis:");
-        print_basis(&basis, 0);
+        println!("basis:");
+        for v in &basis {
+            println!("{:?}", v);
+        }
     }
 
-    let (mut b_star, mut mu_matrix) = gsp(&basis);
+    // 1-indexed as in Cohen: d[0] = 1, lambda[i][j] for j < i
+    let mut d: Vec<Integer> = vec![Integer::from(1); n + 1];
+    let mut lambda: Vec<Vec<Integer>> = (0..=n).map(|i| vec![Integer::from(0); i]).collect();
+    for i in 1..=n {
+        for j in 1..=i {
+            let mut u = inner_product_integer(&basis[i - 1], &basis[j - 1]);
+            for l in 1..j {
+                u = (Integer::from(&d[l] * &u) - Integer::from(&lambda[i][l] * &lambda[j][l]))
+                    / &d[l - 1];
+            }
+            if j < i {
+                lambda[i][j] = u;
+            } else if u == 0 {
+                // |b*_i|^2 = 0, b_i is in the span of the vectors before it
+                if debug {
+                    println!("b{} is linearly dependent, switching to MLLL", i - 1);
+                }
+                return mlll(basis_integer);
+            } else {
+                d[i] = u;
+            }
+        }
+    }
 
     if debug {
-        println!("b*:");
-        print_basis(&b_star, 0);
-        println!("mu:");
-        print_basis(&mu_matrix, 0);
+        println!("d:");
+        println!("{:?}", d);
+        println!("lambda:");
+        for row in &lambda {
+            println!("{:?}", row);
+        }
         println!();
     }
 
-    let mut k = 1;
-    // println!("{:.^1$}", "goal", n); // performance meter for large computations
+    let mut k = 2;
     while k <= n {
-        // print!("\x1b[2K\r");
-        // print!("{:.<1$}", "", k);
-        // io::stdout().flush().unwrap();
-        for j in (0..k).rev() {
+        // size reduce b_k against b_k-1, ..., b_1
+        for l in (1..k).rev() {
             if debug {
-                println!("trying k={} j={} mu={}", k, j, mu_matrix[k][j]);
+                println!("trying k={} l={} lambda={}", k, l, lambda[k][l]);
             }
-            if mu_matrix[k][j] >= 0.5 || mu_matrix[k][j] <= -0.5 {
+            if Integer::from(&lambda[k][l] * 2).abs() >= d[l] {
+                let q = lambda[k][l].clone().div_rem_round(d[l].clone()).0;
                 if debug {
-                    print!(" mu*b_j = [");
+                    println!(" q*b_l = {}*{:?}", q, basis[l - 1]);
                 }
                 for i in 0..basis[0].len() {
-                    if debug {
-                        print!("{:?}, ", Rational::from(&mu_matrix[k][j]).round());
-                    }
-                    let to_subtract = Rational::from(&mu_matrix[k][j]).round() * &basis[j][i];
-                    basis[k][i] -= &to_subtract;
+                    let to_subtract = Integer::from(&q * &basis[l - 1][i]);
+                    basis[k - 1][i] -= to_subtract;
                 }
-                if debug {
-                    println!("]");
-                }
-                gsp_efficient(&basis, &mut b_star, &mut mu_matrix, k);
-
-                if debug {
-                    println!(" rational basis:");
-                    print_basis(&basis, 1);
-                    println!(" b*:");
-                    print_basis(&b_star, 1);
-                    println!("mu:");
-                    print_basis(&mu_matrix, 1);
-                    println!();
+                lambda[k][l] -= Integer::from(&q * &d[l]);
+                let (lower, upper) = lambda.split_at_mut(k);
+                for (target, source) in upper[0][1..l].iter_mut().zip(&lower[l][1..l]) {
+                    *target -= Integer::from(&q * source);
                 }
             }
         }
 
-        if inner_product(&b_star[k], &b_star[k])
-            >= (&delta - Rational::from(mu_matrix[k][k - 1].square_ref()))
-                * inner_product(&b_star[k - 1], &b_star[k - 1])
-        {
+        // Lovász: |b*_k|^2 >= (5/6 - μ^2) |b*_k-1|^2, times 6 d_k-1 d_k-2
+        let lhs = Integer::from(&d[k] * &d[k - 2]) * 6;
+        let rhs = Integer::from(d[k - 1].square_ref()) * 5
+            - Integer::from(lambda[k][k - 1].square_ref()) * 6;
+        if lhs >= rhs {
             k += 1;
             if debug {
                 println!("increment k to {}\n", k);
             }
         } else {
-            basis.swap(k - 1, k);
-            gsp_efficient(&basis, &mut b_star, &mut mu_matrix, k - 1);
-
-            k = std::cmp::max(k - 1, 1);
+            basis.swap(k - 1, k - 2);
+            let (lower, upper) = lambda.split_at_mut(k);
+            lower[k - 1][1..].swap_with_slice(&mut upper[0][1..(k - 1)]);
+            let lam = lambda[k][k - 1].clone();
+            let b =
+                (Integer::from(&d[k - 2] * &d[k]) + Integer::from(lam.square_ref())) / &d[k - 1];
+            for row in lambda.iter_mut().skip(k + 1) {
+                let t = row[k].clone();
+                row[k] =
+                    (Integer::from(&d[k] * &row[k - 1]) - Integer::from(&lam * &t)) / &d[k - 1];
+                row[k - 1] = (Integer::from(&b * &t) + Integer::from(&lam * &row[k])) / &d[k];
+            }
+            d[k - 1] = b;
 
             if debug {
-                println!("swap {} {}", k, k - 1);
-                println!("rational basis:");
-                print_basis(&basis, 1);
-                println!("b*:");
-                print_basis(&b_star, 1);
-                println!("mu:");
-                print_basis(&mu_matrix, 1);
-                println!();
+                println!("swap {} {}", k - 1, k);
+                println!("basis:");
+                for v in &basis {
+                    println!(" {:?}", v);
+                }
+                println!("d:");
+                println!(" {:?}", d);
+            }
+            k = std::cmp::max(k - 1, 2);
+            if debug {
                 println!("k to {}\n", k);
             }
         }
@@ -975,50 +2195,168 @@ pub fn lll(basis_integer: &Vec<Vec<Integer>>) -> (Vec<Vec<Integer>>, usize) {
     }
 
     // check ∀1≤i≤n, j<i. |μ_i,j|≤1/2
-    for i in 0..basis.len() {
-        for j in 0..i {
-            if mu_matrix[i][j] > (1, 2) {
-                println!("mu_{},{} was {:.3}", i, j, mu_matrix[i][j].to_f32());
-            }
-            assert!(mu_matrix[i][j] <= (1, 2));
+    for row in &lambda {
+        for (j, lam) in row.iter().enumerate().skip(1) {
+            assert!(Integer::from(lam * 2).abs() <= d[j]);
         }
     }
 
     // check ∀1≤i<n. δ‖ ̃b_i‖2 ≤ ‖μ_i+1,i  ̃b_i +  ̃b_i+1‖^2
-    for i in 0..(basis.len() - 1) {
-        let lhs = &delta * l2_norm_squared(&b_star[i]);
-        let mu_bi: Vec<Rational> = b_star[i]
-            .iter()
-            .map(|elem| Rational::from(&mu_matrix[i + 1][i] * elem))
-            .collect();
-        let mu_bi_bip1: Vec<Rational> = mu_bi
-            .iter()
-            .zip(&b_star[i + 1])
-            .map(|(lhs, rhs)| Rational::from(lhs + rhs))
-            .collect();
-        let rhs = l2_norm_squared(&mu_bi_bip1);
+    for i in 2..=n {
+        let lhs = Integer::from(&d[i] * &d[i - 2]) * 6;
+        let rhs = Integer::from(d[i - 1].square_ref()) * 5
+            - Integer::from(lambda[i][i - 1].square_ref()) * 6;
+        assert!(lhs >= rhs);
+    }
+
+    return reduced_with_min_idx(basis, &min_norm, debug);
+}
+
+/// LLL for vectors that need not be linearly independent (MLLL, Cohen, Algorithm 2.6.8)
+/// A dependent vector ends up with |b*_k|^2 = 0 and is swapped down and reduced until it is 0,
+/// so the zero vectors collect at the start and the rest is an LLL reduced basis of the same
+/// lattice, with δ = 5/6 as in lll
+/// μ and B_i = |b*_i|^2 are rational and worked out from inner products, without the b*_i
+pub fn mlll(basis_integer: &[Vec<Integer>]) -> (Vec<Vec<Integer>>, usize) {
+    let debug = false;
+
+    let n = basis_integer.len();
+    if n == 0 {
+        return (Vec::new(), 0);
+    }
+    let mut basis = basis_integer.to_vec();
+    let delta = Rational::from((5, 6));
 
-        if lhs > rhs {
-            println!("failed on b_{0} > ub_{0} + b_{0}+1", i)
+    let min_norm = basis
+        .iter()
+        .map(|v| inner_product_integer(v, v))
+        .min()
+        .unwrap();
+
+    // 0-indexed, mu[i][j] for j < i
+    let mut b_norms: Vec<Rational> = vec![Rational::from(0); n];
+    let mut mu: Vec<Vec<Rational>> = (0..n).map(|i| vec![Rational::from(0); i]).collect();
+
+    // μ_k,j = b_k.b*_j / B_j, with b_k.b*_j = b_k.b_j - Σ_i<j μ_j,i μ_k,i B_i
+    let gram_schmidt_row =
+        |basis: &Vec<Vec<Integer>>, b_norms: &mut Vec<Rational>, mu: &mut Vec<Vec<Rational>>, k| {
+            for j in 0..k {
+                if b_norms[j] == 0 {
+                    mu[k][j] = Rational::from(0);
+                    continue;
+                }
+                let mut dot = Rational::from(inner_product_integer(&basis[k], &basis[j]));
+                for i in 0..j {
+                    dot -= Rational::from(&mu[j][i] * &mu[k][i]) * &b_norms[i];
+                }
+                mu[k][j] = dot / &b_norms[j];
+            }
+            let mut b_norm = Rational::from(inner_product_integer(&basis[k], &basis[k]));
+            for j in 0..k {
+                b_norm -= Rational::from(mu[k][j].square_ref()) * &b_norms[j];
+            }
+            b_norms[k] = b_norm;
+        };
+
+    // RED(k, l): b_k -= round(μ_k,l) b_l
+    let reduce =
+        |basis: &mut Vec<Vec<Integer>>, mu: &mut Vec<Vec<Rational>>, k: usize, l: usize| {
+            if Rational::from(&mu[k][l] * 2).abs() <= 1 {
+                return;
+            }
+            let q = Rational::from(mu[k][l].round_ref()).into_numer_denom().0;
+            if debug {
+                println!(" reducing b{} by {}*b{}", k, q, l);
+            }
+            for i in 0..basis[0].len() {
+                let to_subtract = Integer::from(&q * &basis[l][i]);
+                basis[k][i] -= to_subtract;
+            }
+            mu[k][l] -= &q;
+            let (lower, upper) = mu.split_at_mut(k);
+            for (target, source) in upper[0][0..l].iter_mut().zip(&lower[l][0..l]) {
+                *target -= Rational::from(&q * source);
+            }
+        };
+
+    gram_schmidt_row(&basis, &mut b_norms, &mut mu, 0);
+    let mut k_max = 0;
+    let mut k = 1;
+    while k < n {
+        if k > k_max {
+            k_max = k;
+            gram_schmidt_row(&basis, &mut b_norms, &mut mu, k);
+        }
+        reduce(&mut basis, &mut mu, k, k - 1);
+        let bound = (&delta - Rational::from(mu[k][k - 1].square_ref())) * &b_norms[k - 1];
+        if b_norms[k] < bound {
+            // SWAPG(k)
+            if debug {
+                println!("swap {} {}", k - 1, k);
+            }
+            basis.swap(k, k - 1);
+            let (lower, upper) = mu.split_at_mut(k);
+            lower[k - 1][0..].swap_with_slice(&mut upper[0][0..(k - 1)]);
+            let m = mu[k][k - 1].clone();
+            let b = Rational::from(m.square_ref()) * &b_norms[k - 1] + &b_norms[k];
+            if b == 0 {
+                b_norms.swap(k, k - 1);
+                for row in mu.iter_mut().take(k_max + 1).skip(k + 1) {
+                    row.swap(k, k - 1);
+                }
+            } else if b_norms[k] == 0 {
+                b_norms[k - 1] = b;
+                mu[k][k - 1] = Rational::from(m.recip_ref());
+                for row in mu.iter_mut().take(k_max + 1).skip(k + 1) {
+                    row[k - 1] /= &m;
+                }
+            } else {
+                let t = Rational::from(&b_norms[k - 1] / &b);
+                mu[k][k - 1] = Rational::from(&m * &t);
+                b_norms[k] *= &t;
+                b_norms[k - 1] = b;
+                let mu_k_k1 = mu[k][k - 1].clone();
+                for row in mu.iter_mut().take(k_max + 1).skip(k + 1) {
+                    let t = row[k].clone();
+                    row[k] = &row[k - 1] - Rational::from(&m * &t);
+                    row[k - 1] = t + Rational::from(&mu_k_k1 * &row[k]);
+                }
+            }
+            k = std::cmp::max(k - 1, 1);
+        } else {
+            for l in (0..(k - 1)).rev() {
+                reduce(&mut basis, &mut mu, k, l);
+            }
+            k += 1;
         }
-        assert!(lhs <= rhs);
     }
 
-    let basis_output: Vec<Vec<Integer>> = basis
+    let zeros = basis
         .iter()
-        .map(|v| {
-            v.iter()
-                .map(|elem| Rational::from(elem).round().into_numer_denom().0)
-                .collect()
-        })
-        .collect();
+        .take_while(|v| v.iter().all(|x| *x == 0))
+        .count();
+    if debug {
+        println!("dropping {} zero vectors", zeros);
+    }
+    basis.drain(0..zeros);
+    if basis.is_empty() {
+        return (basis, 0);
+    }
+    return reduced_with_min_idx(basis, &min_norm, debug);
+}
 
-    let mut v_norms: Vec<Rational> = Vec::new();
+/// The reduced basis with the index of its shortest vector
+fn reduced_with_min_idx(
+    basis: Vec<Vec<Integer>>,
+    min_norm: &Integer,
+    debug: bool,
+) -> (Vec<Vec<Integer>>, usize) {
+    let mut v_norms: Vec<Integer> = Vec::new();
     if debug {
         println!("min norm^2 is      {}", min_norm);
     }
     for (i, v) in basis.iter().enumerate() {
-        let v_norm = l2_norm_squared(v);
+        let v_norm = inner_product_integer(v, v);
         if debug {
             println!("v{:2} norm^2 is      {}", i, v_norm);
         }
@@ -1030,7 +2368,11 @@ pub fn lll(basis_integer: &Vec<Vec<Integer>>) -> (Vec<Vec<Integer>>, usize) {
         println!("min reduced norm^2 {} (idx {})", min_norm, min_idx);
         println!();
     }
-    return (basis_output, min_idx);
+    return (basis, min_idx);
+}
+
+fn inner_product_integer(u: &[Integer], v: &[Integer]) -> Integer {
+    return u.iter().zip(v).map(|(un, vn)| Integer::from(un * vn)).sum();
 }
 
 pub fn gsp(basis: &Vec<Vec<Rational>>) -> (Vec<Vec<Rational>>, Vec<Vec<Rational>>) {
@@ -1136,19 +2478,6 @@ where
     return u.iter().zip(v).map(|(un, vn)| (un * vn).into()).sum();
 }
 
-fn print_basis(basis: &Vec<Vec<Rational>>, indent: i32) {
-    for vec in basis {
-        for _ in 0..indent {
-            print!(" ");
-        }
-        println!("{:?}", vec);
-    }
-}
-
-fn l2_norm_squared(v: &Vec<Rational>) -> Rational {
-    return inner_product(v, v);
-}
-
 fn derivative(f: &Vec<Integer>, const_x: &Integer) -> Vec<Integer> {
     let mut to_ret = Vec::new();
     for i in 1..f.len() {
@@ -1199,14 +2528,16 @@ pub fn approximate_zero(f: &Vec<Integer>, const_x: &Integer) -> Vec<Integer> {
         loop {
             let denom = eval_rational_lattice_poly(&x, &f_prime, const_x);
             if denom == 0 {
-                println!("breaking");
+                if debug {
+                    println!("breaking");
+                }
                 return results;
             }
             let to_sub = f_of_x / denom;
             x -= &to_sub;
             x = limit_precision(x, 4096);
 
-            if to_sub <= (1, 256) {
+            if to_sub.clone().abs() <= (1, 256) {
                 count += 1;
                 if debug {
                     println!("newton finished in {} steps", count);
@@ -1220,6 +2551,10 @@ pub fn approximate_zero(f: &Vec<Integer>, const_x: &Integer) -> Vec<Integer> {
             }
             f_of_x = eval_rational_lattice_poly(&x, f, const_x);
             count += 1;
+            // Newton can cycle away from any root, so give up on this starting point
+            if count == 200 {
+                break;
+            }
             if debug {
                 println!(
                     " trying x to {:.3} \tf(x) = {:.1e}",
@@ -1,5 +1,6 @@
 use crate::cryptlib;
 use rug::{integer::IsPrime, ops::Pow, Assign, Float, Integer, Rational};
+use std::time::{Duration, Instant};
 
 pub fn eval_poly_bv(f: &Vec<Vec<Integer>>, x: &Integer, y: &Integer, n: &Integer) -> Integer {
     let mut sum = Integer::from(0);
@@ -65,29 +66,35 @@ pub fn multiply_poly_bv(
     if debug {
         print!(" mult {:?}*{:?}", f, g);
     }
-    let mut prod = Vec::new();
     let (f_dx, f_dy) = degree_bv(f);
     let (g_dx, g_dy) = degree_bv(g);
-    for _ in 0..(f_dx + g_dx + 1) {
-        let mut row = Vec::new();
-        for _ in 0..(f_dy + g_dy + 1) {
-            row.push(Integer::from(0));
-        }
-        prod.push(row);
-    }
 
-    for i in 0..(f_dx + 1) {
-        for j in 0..(g_dx + 1) {
-            for u in 0..(f_dy + 1) {
-                for v in 0..(g_dy + 1) {
-                    if *n < 0 {
-                        prod[i + j][u + v] += Integer::from(&f[i][u] * &g[j][v]);
-                    } else {
-                        prod[i + j][u + v] += Integer::from(&f[i][u] * &g[j][v]) % n;
-                    }
+    // pack y into the low part of each x slot, so one univariate product does the work
+    let stride = f_dy + g_dy + 1;
+    let pack = |h: &Vec<Vec<Integer>>, dx: usize, dy: usize| -> Vec<Integer> {
+        let mut packed: Vec<Integer> = (0..(dx * stride + dy + 1))
+            .map(|_| Integer::new())
+            .collect();
+        for (i, row) in h.iter().enumerate().take(dx + 1) {
+            for (j, val) in row.iter().enumerate().take(dy + 1) {
+                packed[i * stride + j].assign(val);
+                if *n > 0 {
+                    packed[i * stride + j] %= n;
                 }
             }
         }
+        packed
+    };
+    let packed = cryptlib::multiply_poly_fast(&pack(f, f_dx, f_dy), &pack(g, g_dx, g_dy));
+
+    let mut prod: Vec<Vec<Integer>> = (0..(f_dx + g_dx + 1))
+        .map(|_| (0..stride).map(|_| Integer::from(0)).collect())
+        .collect();
+    for (idx, mut val) in packed.into_iter().enumerate() {
+        if *n > 0 {
+            val %= n;
+        }
+        prod[idx / stride][idx % stride] = val;
     }
     if debug {
         println!("\t|\t   result {:?}", prod);
@@ -107,14 +114,19 @@ pub fn exp_poly_bv(f: &Vec<Vec<Integer>>, e: &Integer, n: &Integer) -> Vec<Vec<I
     }
     let mut result = f.clone();
 
-    for _ in 0..Integer::from(e - 1).to_i32().unwrap() {
-        result = multiply_poly_bv(&result, f, n);
+    // left to right square and multiply, starting below the top bit of e
+    for i in (0..(e.significant_bits() - 1)).rev() {
+        result = multiply_poly_bv(&result, &result, n);
+        if e.get_bit(i) {
+            result = multiply_poly_bv(&result, f, n);
+        }
     }
     return result;
 }
 
 /// Finds a small root (x0, y0) of f where x0 < X, y0 < Y
-/// larger values of k allow for larger values of X, Y
+/// larger values of k allow for larger values of X, Y, and k = 0 is rejected
+/// When f(0, 0) = 0 that trivial root is skipped
 pub fn coppersmith_bv(
     f: &Vec<Vec<Integer>>,
     cap_x: &Integer,
@@ -123,14 +135,237 @@ pub fn coppersmith_bv(
 ) -> Option<(Integer, Integer)> {
     let debug = false;
 
-    //remove gcd if present
+    if k == 0 {
+        return None;
+    }
+    if debug {
+        let (log2_xy, log2_bound) = coron_bound(f, cap_x, cap_y, k);
+        println!(
+            " condition {}: {:.4}",
+            log2_xy < log2_bound,
+            (log2_xy - log2_bound) * std::f64::consts::LOG10_2
+        );
+    }
+    return coron_attempt(f, cap_x, cap_y, k, None);
+}
+
+/// What Coron's method is expected to need for a given k, worked out before reducing anything
+#[derive(Debug, Clone)]
+pub struct CoronPlan {
+    pub k: usize,
+    /// lattice dimension (𝛿 + k + 1)^2
+    pub dimension: usize,
+    /// log2 of W^(2/(3𝛿) - 1/k), the bound XY has to stay under for this k
+    pub log2_bound: f64,
+    pub bound_met: bool,
+    /// rough LLL time from the dimension and the size of the lattice entries
+    pub estimated_time: Duration,
+}
+
+/// One lattice reduction tried by coppersmith_bv_auto
+#[derive(Debug, Clone)]
+pub struct CoronAttempt {
+    pub plan: CoronPlan,
+    pub elapsed: Duration,
+    pub root: Option<(Integer, Integer)>,
+}
+
+/// Everything coppersmith_bv_auto decided and measured
+#[derive(Debug, Clone)]
+pub struct CoronReport {
+    pub delta: usize,
+    pub log2_w: f64,
+    pub log2_xy: f64,
+    /// smallest k up to max_k with XY < W^(2/(3𝛿) - 1/k), None if there is none
+    pub k_bound: Option<usize>,
+    pub attempts: Vec<CoronAttempt>,
+    pub root: Option<(Integer, Integer)>,
+}
+
+/// Seconds per unit of ω^4 * bits^2, fitted to cryptlib::lll for ω from 16 to 36
+const LLL_SECONDS_PER_UNIT: f64 = 3.8e-10;
+
+/// Works out the lattice dimension, Coron's bound and the expected LLL time for k
+pub fn coron_plan(f: &Vec<Vec<Integer>>, cap_x: &Integer, cap_y: &Integer, k: usize) -> CoronPlan {
+    let (cap_w, delta) = coron_parameters(f, cap_x, cap_y);
+    let (log2_xy, log2_bound) = coron_bound(f, cap_x, cap_y, k);
+    let dimension = (delta + k + 1).pow(2);
+    // entries are at most n X^(k+𝛿) Y^(k+𝛿) with n ~ (XY)^k W
+    let bits = log2(&cap_w) + (2 * k + delta) as f64 * log2_xy;
+    let estimated_time =
+        Duration::from_secs_f64(LLL_SECONDS_PER_UNIT * (dimension as f64).powi(4) * bits.powi(2));
+    return CoronPlan {
+        k,
+        dimension,
+        log2_bound,
+        bound_met: log2_xy < log2_bound,
+        estimated_time,
+    };
+}
+
+/// Runs Coron's method starting from the smallest k meeting XY < W^(2/(3𝛿) - ε) with ε = 1/k
+/// (or k = 1 if no k up to max_k does), and retries with k + 1 until a root is found or k > max_k
+pub fn coppersmith_bv_auto(
+    f: &Vec<Vec<Integer>>,
+    cap_x: &Integer,
+    cap_y: &Integer,
+    max_k: usize,
+) -> CoronReport {
+    let (cap_w, delta) = coron_parameters(f, cap_x, cap_y);
+    let mut report = CoronReport {
+        delta,
+        log2_w: log2(&cap_w),
+        log2_xy: log2(cap_x) + log2(cap_y),
+        k_bound: (1..=max_k).find(|&k| coron_plan(f, cap_x, cap_y, k).bound_met),
+        attempts: Vec::new(),
+        root: None,
+    };
+
+    for k in report.k_bound.unwrap_or(1)..=max_k {
+        let plan = coron_plan(f, cap_x, cap_y, k);
+        let now = Instant::now();
+        let root = coron_attempt(f, cap_x, cap_y, k, None);
+        report.attempts.push(CoronAttempt {
+            plan,
+            elapsed: now.elapsed(),
+            root: root.clone(),
+        });
+        if root.is_some() {
+            report.root = root;
+            break;
+        }
+    }
+    return report;
+}
+
+fn log2(a: &Integer) -> f64 {
+    return Float::with_val(64, a).log2().to_f64();
+}
+
+/// log2(XY) and log2(W^(2/(3𝛿) - 1/k)), Coron's bound with ε = 1/k (no bound at all for k = 0)
+fn coron_bound(f: &Vec<Vec<Integer>>, cap_x: &Integer, cap_y: &Integer, k: usize) -> (f64, f64) {
+    let (f, cap_x, cap_y, _, _) = translate_constant_bv(f, cap_x, cap_y);
+    let (cap_w, delta) = coron_parameters(&f, &cap_x, &cap_y);
+    if k == 0 {
+        return (log2(&cap_x) + log2(&cap_y), f64::NEG_INFINITY);
+    }
+    let exponent = 2.0 / (3 * delta) as f64 - 1.0 / k as f64;
+    return (log2(&cap_x) + log2(&cap_y), exponent * log2(&cap_w));
+}
+
+/// W = max |a_ij| X^i Y^j of f with its content removed, and 𝛿 = max(deg_x f, deg_y f)
+fn coron_parameters(f: &Vec<Vec<Integer>>, cap_x: &Integer, cap_y: &Integer) -> (Integer, usize) {
+    let (f, cap_x, cap_y, _, _) = translate_constant_bv(f, cap_x, cap_y);
+    let f = &f;
     let mut gcd = Integer::from(0);
     for row in f {
         for elem in row {
             gcd = cryptlib::gcd(&gcd, elem);
         }
     }
-    let mut f = f.clone();
+    let mut cap_w = Integer::from(0);
+    for (i, row) in f.iter().enumerate() {
+        for (j, elem) in row.iter().enumerate() {
+            let val = (cap_x.clone().pow(i as u32) * cap_y.clone().pow(j as u32) * elem).abs();
+            cap_w = cap_w.max(val);
+        }
+    }
+    if gcd > 1 {
+        cap_w /= &gcd;
+    }
+    let (dx, dy) = degree_bv(f);
+    return (cap_w, std::cmp::max(dx, dy));
+}
+
+/// Returns f(x + a, y + b)
+pub fn translate_poly_bv(f: &[Vec<Integer>], a: &Integer, b: &Integer) -> Vec<Vec<Integer>> {
+    let width = f.iter().map(|row| row.len()).max().unwrap_or(0);
+    let mut result: Vec<Vec<Integer>> = (0..f.len())
+        .map(|_| (0..width).map(|_| Integer::from(0)).collect())
+        .collect();
+    for (i, row) in f.iter().enumerate() {
+        for (j, elem) in row.iter().enumerate() {
+            if *elem == 0 {
+                continue;
+            }
+            // x^i y^j = sum C(i, s) a^(i-s) x^s * sum C(j, t) b^(j-t) y^t
+            for (s, result_row) in result.iter_mut().enumerate().take(i + 1) {
+                let x_part = Integer::from(Integer::binomial_u(i as u32, s as u32))
+                    * a.clone().pow((i - s) as u32)
+                    * elem;
+                for (t, coef) in result_row.iter_mut().enumerate().take(j + 1) {
+                    *coef += Integer::from(Integer::binomial_u(j as u32, t as u32))
+                        * b.clone().pow((j - t) as u32)
+                        * &x_part;
+                }
+            }
+        }
+    }
+    return result;
+}
+
+/// Coron's lattice needs p_0,0 != 0, so when it is zero f is translated by the first (a, b) in
+/// [0, 𝛿]^2 with f(a, b) != 0 (one exists since f is nonzero of degree 𝛿 in each variable),
+/// and X, Y grow by a, b so the translated root (x0 - a, y0 - b) stays within them
+fn translate_constant_bv(
+    f: &Vec<Vec<Integer>>,
+    cap_x: &Integer,
+    cap_y: &Integer,
+) -> (Vec<Vec<Integer>>, Integer, Integer, Integer, Integer) {
+    let zero = Integer::from(0);
+    if f.first()
+        .and_then(|row| row.first())
+        .is_some_and(|elem| *elem != 0)
+    {
+        return (f.clone(), cap_x.clone(), cap_y.clone(), zero.clone(), zero);
+    }
+    let (dx, dy) = degree_bv(f);
+    let delta = std::cmp::max(dx, dy);
+    for a in 0..=delta {
+        for b in 0..=delta {
+            let (a, b) = (Integer::from(a), Integer::from(b));
+            if eval_poly_bv(f, &a, &b, &Integer::from(-1)) != 0 {
+                return (
+                    translate_poly_bv(f, &a, &b),
+                    Integer::from(cap_x + &a),
+                    Integer::from(cap_y + &b),
+                    a,
+                    b,
+                );
+            }
+        }
+    }
+    panic!("f is the zero polynomial");
+}
+
+/// The lattice reduction behind coppersmith_bv for a single k
+fn coron_attempt(
+    f: &Vec<Vec<Integer>>,
+    cap_x: &Integer,
+    cap_y: &Integer,
+    k: usize,
+    trivial_root: Option<(Integer, Integer)>,
+) -> Option<(Integer, Integer)> {
+    let debug = false;
+
+    let (f, cap_x, cap_y, a, b) = translate_constant_bv(f, cap_x, cap_y);
+    if a != 0 || b != 0 {
+        if debug {
+            println!("translated by ({}, {}) to make p_0,0 nonzero", a, b);
+        }
+        // (0, 0) is a root of f, moved to (-a, -b), and small enough to be found instead
+        let trivial_root = Some((Integer::from(-&a), Integer::from(-&b)));
+        return coron_attempt(&f, &cap_x, &cap_y, k, trivial_root).map(|(x, y)| (x + a, y + b));
+    }
+
+    //remove gcd if present
+    let mut gcd = Integer::from(0);
+    for row in &f {
+        for elem in row {
+            gcd = cryptlib::gcd(&gcd, elem);
+        }
+    }
+    let mut f = f;
     if gcd > 1 {
         if debug {
             println!("changing f from\n{:?}", f);
@@ -169,12 +404,23 @@ pub fn coppersmith_bv(
         );
     }
 
-    // unsure
-    let epsilon_recip = 10;
-
     let (dx, dy) = degree_bv(&f);
     let delta = std::cmp::max(dx, dy);
 
+    // the shifts below index monomials of the dense box [0, 𝛿]^2, whatever shape f came in
+    let f: Vec<Vec<Integer>> = (0..=delta)
+        .map(|i| {
+            (0..=delta)
+                .map(|j| {
+                    f.get(i)
+                        .and_then(|row| row.get(j))
+                        .cloned()
+                        .unwrap_or_default()
+                })
+                .collect()
+        })
+        .collect();
+
     // W = max a_ij X^i Y^j
     let cap_w = f
         .iter()
@@ -232,13 +478,6 @@ pub fn coppersmith_bv(
         println!();
     }
 
-    let lhs = Float::with_val(1024, cap_x.clone() * &cap_y);
-    let w_pow = (2 * epsilon_recip - 3 * delta) as u32;
-    let w_root = (3 * epsilon_recip * delta) as u32;
-    let rhs = Float::with_val(1024, cap_w.clone()).pow(w_pow).root(w_root);
-
-    println!(" condition {}: {:.4}", lhs < rhs, (lhs / rhs).log10());
-
     assert!(f[0][0] != 0, "p_0,0 = 0");
 
     let inverse = cryptlib::find_inverse(&f[0][0], &n);
@@ -267,8 +506,8 @@ pub fn coppersmith_bv(
         .map(|i| {
             (0..=(k + delta))
                 .map(|j| {
-                    let mut new_q: Vec<Vec<Integer>> = (0..=(dx + k))
-                        .map(|_| (0..=(dy + k)).map(|_| Integer::from(0)).collect())
+                    let mut new_q: Vec<Vec<Integer>> = (0..=(delta + k))
+                        .map(|_| (0..=(delta + k)).map(|_| Integer::from(0)).collect())
                         .collect();
                     if i <= k && j <= k {
                         let cap_x_pow = cap_x.clone().pow((k - i) as u32);
@@ -334,99 +573,100 @@ pub fn coppersmith_bv(
         }
     }
 
-    let mut normalized_h = reduced[0].clone();
-    for i in 0..(dx + k) {
-        for j in 0..(dy + k) {
-            normalized_h[j * (dx + k) + i] /= cap_x.clone().pow(i as u32);
-            normalized_h[j * (dx + k) + i] /= cap_y.clone().pow(j as u32);
-        }
-    }
-
     let reversed_f: Vec<Vec<Integer>> = (0..=dy)
         .map(|j| (0..=dx).map(|i| f[i][j].clone()).collect())
         .collect();
-    let reversed_h: Vec<Vec<Integer>> = (0..=(dy + k))
-        .map(|j| {
-            (0..=(dx + k))
-                .map(|i| {
-                    reduced[0][i * (dx + k + 1) + j].clone()
-                        / cap_x.clone().pow(i as u32)
-                        / cap_y.clone().pow(j as u32)
-                })
-                .collect()
-        })
-        .collect();
-    if debug {
-        println!("h^T {:?}", reversed_h);
-        println!("f^T {:?}", reversed_f);
-    }
-    let mut cap_q = cryptlib::resultant(&reversed_h, &reversed_f, &Integer::from(-1));
-    let mut gcd = Integer::from(0);
-    for coef in &cap_q {
-        gcd = cryptlib::gcd(coef, &gcd);
-    }
-    if debug {
-        println!("gcd: {}", gcd);
-    }
+    // Coron only needs some short h that is not a multiple of f, and the shortest vector can be
+    // a multiple of f or too long to vanish over the integers, so try them in order
+    for h in &reduced {
+        let reversed_h: Vec<Vec<Integer>> = (0..=(delta + k))
+            .map(|j| {
+                (0..=(delta + k))
+                    .map(|i| {
+                        h[i * (delta + k + 1) + j].clone()
+                            / cap_x.clone().pow(i as u32)
+                            / cap_y.clone().pow(j as u32)
+                    })
+                    .collect()
+            })
+            .collect();
+        if debug {
+            println!("h^T {:?}", reversed_h);
+            println!("f^T {:?}", reversed_f);
+        }
+        let mut cap_q =
+            cryptlib::resultant_interpolate(&reversed_h, &reversed_f, &Integer::from(-1)).unwrap();
+        let mut gcd = Integer::from(0);
+        for coef in &cap_q {
+            gcd = cryptlib::gcd(coef, &gcd);
+        }
+        if debug {
+            println!("gcd: {}", gcd);
+        }
+        // h shares a factor with f, so it says nothing about x
+        if gcd == 0 {
+            continue;
+        }
 
-    for (i, coef) in cap_q.iter_mut().enumerate() {
-        *coef /= &gcd;
-        *coef *= cap_x.clone().pow(i as u32);
-    }
+        for (i, coef) in cap_q.iter_mut().enumerate() {
+            *coef /= &gcd;
+            *coef *= cap_x.clone().pow(i as u32);
+        }
 
-    if debug {
-        println!("Q {:?}", cap_q);
-    }
+        if debug {
+            println!("Q {:?}", cap_q);
+        }
 
-    let x_candidates = cryptlib::approximate_zero(&cap_q, &cap_x);
+        let x_candidates = cryptlib::approximate_zero(&cap_q, &cap_x);
 
-    for (i, coef) in cap_q.iter_mut().enumerate() {
-        *coef /= cap_x.clone().pow(i as u32);
-    }
+        for (i, coef) in cap_q.iter_mut().enumerate() {
+            *coef /= cap_x.clone().pow(i as u32);
+        }
 
-    let search_range = 5;
-    let mut x_val;
-    for candidate in &x_candidates {
-        for i in -search_range..=search_range {
-            let x = Integer::from(candidate + i);
-            let f_of_x = cryptlib::eval_poly(&x, &cap_q, &Integer::from(-1));
-            if f_of_x == 0 {
-                if debug {
-                    println!("f({}) = 0", x);
-                }
-                x_val = x;
+        let search_range = 5;
+        let mut x_val;
+        for candidate in &x_candidates {
+            for i in -search_range..=search_range {
+                let x = Integer::from(candidate + i);
+                let f_of_x = cryptlib::eval_poly(&x, &cap_q, &Integer::from(-1));
+                if f_of_x == 0 {
+                    if debug {
+                        println!("f({}) = 0", x);
+                    }
+                    x_val = x;
 
-                let mut y_poly: Vec<Integer> = reversed_f
-                    .iter()
-                    .map(|term| cryptlib::eval_poly(&x_val, term, &Integer::from(-1)))
-                    .collect();
+                    let mut y_poly: Vec<Integer> = reversed_f
+                        .iter()
+                        .map(|term| cryptlib::eval_poly(&x_val, term, &Integer::from(-1)))
+                        .collect();
 
-                if debug {
-                    println!("poly for y: {:?}", y_poly);
-                }
+                    if debug {
+                        println!("poly for y: {:?}", y_poly);
+                    }
 
-                for (i, coef) in y_poly.iter_mut().enumerate() {
-                    *coef *= cap_y.clone().pow(i as u32);
-                }
+                    for (i, coef) in y_poly.iter_mut().enumerate() {
+                        *coef *= cap_y.clone().pow(i as u32);
+                    }
 
-                let y_candidates = cryptlib::approximate_zero(&y_poly, &cap_y);
+                    let y_candidates = cryptlib::approximate_zero(&y_poly, &cap_y);
 
-                for (i, coef) in y_poly.iter_mut().enumerate() {
-                    *coef /= cap_y.clone().pow(i as u32);
-                }
+                    for (i, coef) in y_poly.iter_mut().enumerate() {
+                        *coef /= cap_y.clone().pow(i as u32);
+                    }
 
-                let y_val;
-                for candidate in &y_candidates {
-                    // println!("trying {}", candidate);
-                    for i in -search_range..=search_range {
-                        let y = Integer::from(candidate + i);
-                        let f_of_y = cryptlib::eval_poly(&y, &y_poly, &Integer::from(-1));
-                        if f_of_y == 0 {
-                            if debug {
-                                println!("f({}) = 0", y);
+                    let y_val;
+                    for candidate in &y_candidates {
+                        // println!("trying {}", candidate);
+                        for i in -search_range..=search_range {
+                            let y = Integer::from(candidate + i);
+                            let f_of_y = cryptlib::eval_poly(&y, &y_poly, &Integer::from(-1));
+                            if f_of_y == 0 && trivial_root != Some((x_val.clone(), y.clone())) {
+                                if debug {
+                                    println!("f({}) = 0", y);
+                                }
+                                y_val = y;
+                                return Some((x_val, y_val));
                             }
-                            y_val = y;
-                            return Some((x_val, y_val));
                         }
                     }
                 }
@@ -0,0 +1,848 @@
+use crate::cryptlib;
+use rug::{ops::Pow, rand::RandState, Integer, Rational};
+
+/// Above this many modular factors, recombination tries van Hoeij's knapsack before subsets
+const ZASSENHAUS_MAX_FACTORS: usize = 6;
+
+/// Factors f over Z[x], returning (content, [(irreducible primitive factor, multiplicity)])
+/// The content carries the sign so that every factor has a positive leading coefficient
+pub fn factor_poly_z(f: &[Integer]) -> (Integer, Vec<(Vec<Integer>, u32)>) {
+    let debug = false;
+
+    let f = trim(f.to_vec());
+    if f.is_empty() {
+        return (Integer::from(0), Vec::new());
+    }
+    let mut cont = content(&f);
+    let mut f = primitive_part(&f);
+    if f[f.len() - 1] < 0 {
+        cont = -cont;
+        f = f.into_iter().map(|x| -x).collect();
+    }
+
+    let mut factors = Vec::new();
+    for (part, multiplicity) in squarefree_decomposition(&f) {
+        if debug {
+            println!("squarefree part {:?} ^{}", part, multiplicity);
+        }
+        // pull out factors of x first, they break the modular root counts
+        let mut part = part;
+        let mut x_power = 0;
+        while part.len() > 1 && part[0] == 0 {
+            part.remove(0);
+            x_power += 1;
+        }
+        if x_power > 0 {
+            let x = vec![Integer::from(0), Integer::from(1)];
+            factors.push((x, multiplicity * x_power));
+        }
+        if part.len() < 2 {
+            continue;
+        }
+        for factor in factor_squarefree(&part) {
+            factors.push((factor, multiplicity));
+        }
+    }
+    factors.sort_by(|a, b| a.0.len().cmp(&b.0.len()).then(a.0.cmp(&b.0)));
+    return (cont, factors);
+}
+
+/// Returns the distinct integer roots of f, read off its linear factors over Z
+pub fn integer_roots(f: &[Integer]) -> Vec<Integer> {
+    let (_cont, factors) = factor_poly_z(f);
+    let mut roots = Vec::new();
+    for (factor, _multiplicity) in factors {
+        if factor.len() == 2 && factor[0].is_divisible(&factor[1]) {
+            roots.push(-Integer::from(factor[0].div_exact_ref(&factor[1])));
+        }
+    }
+    roots.sort();
+    return roots;
+}
+
+/// Factors a squarefree f mod a prime p > 2 into monic irreducible factors (Cantor-Zassenhaus)
+pub fn factor_poly_zp(f: &[Integer], p: &Integer) -> Vec<Vec<Integer>> {
+    let f = monic_mod(&reduce(f, p), p);
+    let mut rand = RandState::new();
+    let mut factors = Vec::new();
+    for (part, d) in distinct_degree_factor(&f, p) {
+        equal_degree_factor(&part, d, p, &mut rand, &mut factors);
+    }
+    factors.sort();
+    return factors;
+}
+
+/// Lifts f = lc(f) * prod(factors) mod p to a factorization mod p^a
+/// factors must be monic, pairwise coprime mod p, and lc(f) must be a unit mod p
+pub fn hensel_lift(
+    f: &[Integer],
+    factors: &[Vec<Integer>],
+    p: &Integer,
+    a: u32,
+) -> Vec<Vec<Integer>> {
+    let modulus = Integer::from(p.pow(a));
+    let f = reduce(f, &modulus);
+    if factors.len() == 1 {
+        return vec![monic_mod(&f, &modulus)];
+    }
+    let k = factors.len() / 2;
+    let lc = Integer::from(&f[f.len() - 1] % p);
+    let mut g = vec![lc];
+    for factor in &factors[..k] {
+        g = mul_mod(&g, factor, p);
+    }
+    let mut h = vec![Integer::from(1)];
+    for factor in &factors[k..] {
+        h = mul_mod(&h, factor, p);
+    }
+    let (lifted_g, lifted_h) = hensel_lift_pair(&f, &g, &h, p, a);
+
+    let mut lifted = hensel_lift(&lifted_g, &factors[..k], p, a);
+    lifted.append(&mut hensel_lift(&lifted_h, &factors[k..], p, a));
+    return lifted;
+}
+
+/// Quadratic Hensel lifting of f = g * h mod p to mod p^a, with h monic
+fn hensel_lift_pair(
+    f: &[Integer],
+    g: &[Integer],
+    h: &[Integer],
+    p: &Integer,
+    a: u32,
+) -> (Vec<Integer>, Vec<Integer>) {
+    let (one, mut s, mut t) = ext_gcd_mod(g, h, p);
+    assert!(
+        one == vec![Integer::from(1)],
+        "factors are not coprime mod p"
+    );
+
+    let mut g = g.to_vec();
+    let mut h = h.to_vec();
+    let mut m = p.clone();
+    let target = Integer::from(p.pow(a));
+    while m < target {
+        let m2 = Integer::from(m.square_ref());
+
+        let e = sub_mod(&reduce(f, &m2), &mul_mod(&g, &h, &m2), &m2);
+        let (q, r) = divrem_mod(&mul_mod(&s, &e, &m2), &h, &m2);
+        let new_g = add_mod(
+            &add_mod(&g, &mul_mod(&t, &e, &m2), &m2),
+            &mul_mod(&q, &g, &m2),
+            &m2,
+        );
+        let new_h = add_mod(&h, &r, &m2);
+
+        let b = sub_mod(
+            &add_mod(&mul_mod(&s, &new_g, &m2), &mul_mod(&t, &new_h, &m2), &m2),
+            &[Integer::from(1)],
+            &m2,
+        );
+        let (c, d) = divrem_mod(&mul_mod(&s, &b, &m2), &new_h, &m2);
+        s = sub_mod(&s, &d, &m2);
+        t = sub_mod(
+            &sub_mod(&t, &mul_mod(&t, &b, &m2), &m2),
+            &mul_mod(&c, &new_g, &m2),
+            &m2,
+        );
+        g = new_g;
+        h = new_h;
+        m = m2;
+    }
+    return (reduce(&g, &target), reduce(&h, &target));
+}
+
+/// Factors a primitive squarefree polynomial with positive leading coefficient and f(0) != 0
+fn factor_squarefree(f: &[Integer]) -> Vec<Vec<Integer>> {
+    let debug = false;
+
+    let n = f.len() - 1;
+    if n == 1 {
+        return vec![f.to_vec()];
+    }
+
+    // try a few good primes and keep the one with the fewest modular factors
+    let mut best: Option<(Integer, Vec<Vec<Integer>>)> = None;
+    let mut p = Integer::from(2);
+    let mut tried = 0;
+    while tried < 5 {
+        p.next_prime_mut();
+        if f[n].is_divisible(&p) {
+            continue;
+        }
+        let f_p = reduce(f, &p);
+        if gcd_mod(&f_p, &reduce(&derivative_z(f), &p), &p).len() > 1 {
+            continue;
+        }
+        tried += 1;
+        let modular = factor_poly_zp(f, &p);
+        if debug {
+            println!("p = {}: {} modular factors", p, modular.len());
+        }
+        if best.as_ref().is_none_or(|(_, b)| modular.len() < b.len()) {
+            best = Some((p.clone(), modular));
+        }
+        if best.as_ref().unwrap().1.len() == 1 {
+            break;
+        }
+    }
+    let (p, modular) = best.unwrap();
+    if modular.len() == 1 {
+        return vec![f.to_vec()];
+    }
+
+    // Mignotte: every factor of f has coefficients below 2^n * ||f||_2
+    let norm = f.iter().map(|x| x.clone().square()).sum::<Integer>().sqrt() + 1;
+    let bound = Integer::from(2).pow(n as u32) * norm * &f[n] * 2;
+    let mut a = 1;
+    while Integer::from((&p).pow(a)) <= bound {
+        a += 1;
+    }
+    if modular.len() > ZASSENHAUS_MAX_FACTORS {
+        a = std::cmp::max(a, knapsack_digits(f, modular.len(), &p));
+    }
+    let lifted = hensel_lift(f, &modular, &p, a);
+    let modulus = Integer::from((&p).pow(a));
+    if debug {
+        println!("lifted {} factors mod {}^{}", lifted.len(), p, a);
+    }
+
+    let mut factors = Vec::new();
+    let mut remaining = f.to_vec();
+    let mut lifted = lifted;
+    if lifted.len() > ZASSENHAUS_MAX_FACTORS {
+        if let Some(parts) = van_hoeij_partition(f, &lifted, &p, a) {
+            let mut found = Vec::new();
+            for part in &parts {
+                let chosen: Vec<&Vec<Integer>> = part.iter().map(|i| &lifted[*i]).collect();
+                match combine(&remaining, &chosen, &modulus) {
+                    Some((factor, quotient)) => {
+                        found.push(factor);
+                        remaining = quotient;
+                    }
+                    None => break,
+                }
+            }
+            if found.len() == parts.len() {
+                return found;
+            }
+            if debug {
+                println!("knapsack partition failed, falling back to subsets");
+            }
+            remaining = f.to_vec();
+        }
+    }
+
+    // Zassenhaus: try subsets of increasing size
+    let mut size = 1;
+    while 2 * size <= lifted.len() {
+        let mut found = false;
+        for subset in combinations(lifted.len(), size) {
+            let chosen: Vec<&Vec<Integer>> = subset.iter().map(|i| &lifted[*i]).collect();
+            if let Some((factor, quotient)) = combine(&remaining, &chosen, &modulus) {
+                factors.push(factor);
+                remaining = quotient;
+                lifted = lifted
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(i, _)| !subset.contains(i))
+                    .map(|(_, g)| g)
+                    .collect();
+                found = true;
+                break;
+            }
+        }
+        if !found {
+            size += 1;
+        }
+    }
+    factors.push(remaining);
+    return factors;
+}
+
+/// Builds lc(f) * prod(chosen) mod p^a, and returns it with the quotient if it divides f over Z
+fn combine(
+    f: &[Integer],
+    chosen: &[&Vec<Integer>],
+    modulus: &Integer,
+) -> Option<(Vec<Integer>, Vec<Integer>)> {
+    let lc = f[f.len() - 1].clone();
+    let mut g = vec![lc];
+    for factor in chosen {
+        g = mul_mod(&g, factor, modulus);
+    }
+    let g: Vec<Integer> = g.iter().map(|x| symmetric_mod(x, modulus)).collect();
+    let g = primitive_part(&trim(g));
+    if g.len() < 2 {
+        return None;
+    }
+    return divide_exact_z(f, &g).map(|quotient| (g, quotient));
+}
+
+/// Number of p-adic digits the knapsack lattice needs: the trace bounds plus a separating margin
+fn knapsack_digits(f: &[Integer], r: usize, p: &Integer) -> u32 {
+    let n = f.len() - 1;
+    let lc = &f[n];
+    // Cauchy: every root of f has |a| < 1 + max |f_i / lc|, so |lc * a| < |lc| + max |f_i|
+    let root_bound = f.iter().map(|x| x.clone().abs()).max().unwrap() + lc;
+    // bound on |lc^j * power sum j| over any true factor, for the largest j used
+    let bound = Integer::from(n) * root_bound.pow(n as u32);
+    let mut b = 0;
+    while Integer::from(p.pow(b)) <= bound {
+        b += 1;
+    }
+    return b + separation_digits(r, n, p);
+}
+
+/// Digits kept above the cut, enough to separate vectors of length ~r despite LLL's 2^(d/2) slack
+fn separation_digits(r: usize, n: usize, p: &Integer) -> u32 {
+    let dim = (r + n) as u32;
+    let separation = Integer::from(2).pow(dim) * (dim * dim) * (r * r) as u32;
+    let mut digits = 0;
+    while Integer::from(p.pow(digits)) <= separation {
+        digits += 1;
+    }
+    return digits;
+}
+
+/// van Hoeij: reduce the knapsack lattice of power sums to find which modular factors combine
+/// Returns the partition of the lifted factors into the true factors, if the lattice reveals one
+fn van_hoeij_partition(
+    f: &[Integer],
+    lifted: &[Vec<Integer>],
+    p: &Integer,
+    a: u32,
+) -> Option<Vec<Vec<usize>>> {
+    let debug = false;
+
+    let n = f.len() - 1;
+    let r = lifted.len();
+    let lc = &f[n];
+    let modulus = Integer::from(p.pow(a));
+
+    // power sums of (lc * root) over each modular factor, which are small integers for true factors
+    let traces: Vec<Vec<Integer>> = lifted
+        .iter()
+        .map(|g| {
+            let sums = power_sums(g, n, &modulus);
+            let mut lc_power = Integer::from(1);
+            sums.iter()
+                .map(|x| {
+                    lc_power *= lc;
+                    lc_power %= &modulus;
+                    symmetric_mod(&Integer::from(x * &lc_power), &modulus)
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut s = std::cmp::min(n, 2);
+    loop {
+        // rows [e_i | T_ij / p^cut] and [0 | p^(a - cut) e_j]: true traces lie below p^cut,
+        // so cutting those digits leaves only rounding errors of at most r / 2
+        let dim = r + s;
+        let cut = a - separation_digits(r, n, p);
+        let divisor = Integer::from(p.pow(cut));
+        let mut basis = Vec::new();
+        for i in 0..r {
+            let mut row = vec![Integer::from(0); dim];
+            row[i] = Integer::from(1);
+            for j in 0..s {
+                row[r + j] = traces[i][j].clone().div_rem_round(divisor.clone()).0;
+            }
+            basis.push(row);
+        }
+        for j in 0..s {
+            let mut row = vec![Integer::from(0); dim];
+            row[r + j] = Integer::from(p.pow(a - cut));
+            basis.push(row);
+        }
+
+        let (reduced, _min_idx) = cryptlib::lll(&basis);
+
+        // true factors have indicator vectors with cut traces of size at most r / 2 + 1,
+        // so drop the trailing vectors whose Gram-Schmidt length exceeds that
+        let threshold = Rational::from(r + s * (r / 2 + 2) * (r / 2 + 2));
+        let rational: Vec<Vec<Rational>> = reduced
+            .iter()
+            .map(|v| v.iter().map(Rational::from).collect())
+            .collect();
+        let (b_star, _mu) = cryptlib::gsp(&rational);
+        let mut keep = reduced.len();
+        while keep > 0 {
+            let norm: Rational = b_star[keep - 1]
+                .iter()
+                .map(|x| Rational::from(x.square_ref()))
+                .sum();
+            if norm <= threshold {
+                break;
+            }
+            keep -= 1;
+        }
+        if debug {
+            println!(
+                "knapsack with {} traces: {} of {} vectors kept",
+                s, keep, dim
+            );
+        }
+
+        if keep > 0 {
+            let short: Vec<Vec<Integer>> =
+                reduced[..keep].iter().map(|v| v[..r].to_vec()).collect();
+            if let Some(parts) = indicator_partition(&short, r) {
+                return Some(parts);
+            }
+        }
+        if s == n {
+            return None;
+        }
+        s = std::cmp::min(n, 2 * s);
+    }
+}
+
+/// Reads the 0/1 indicator vectors of a partition of 0..r off a basis of their span
+fn indicator_partition(rows: &[Vec<Integer>], r: usize) -> Option<Vec<Vec<usize>>> {
+    let echelon = row_echelon(rows);
+    let mut parts = Vec::new();
+    let mut covered = vec![false; r];
+    for row in echelon {
+        let mut part = Vec::new();
+        for (i, val) in row.iter().enumerate() {
+            if *val == 1 {
+                if covered[i] {
+                    return None;
+                }
+                covered[i] = true;
+                part.push(i);
+            } else if *val != 0 {
+                return None;
+            }
+        }
+        if !part.is_empty() {
+            parts.push(part);
+        }
+    }
+    if covered.iter().any(|c| !*c) {
+        return None;
+    }
+    return Some(parts);
+}
+
+/// Reduced row echelon form over Q, with non-integral entries replaced by -1
+fn row_echelon(rows: &[Vec<Integer>]) -> Vec<Vec<Integer>> {
+    let mut matrix: Vec<Vec<Rational>> = rows
+        .iter()
+        .map(|row| row.iter().map(Rational::from).collect())
+        .collect();
+    let cols = if matrix.is_empty() {
+        0
+    } else {
+        matrix[0].len()
+    };
+    let mut pivot_row = 0;
+    for col in 0..cols {
+        let pivot = (pivot_row..matrix.len()).find(|i| matrix[*i][col] != 0);
+        let pivot = match pivot {
+            Some(i) => i,
+            None => continue,
+        };
+        matrix.swap(pivot_row, pivot);
+        let lead = matrix[pivot_row][col].clone();
+        for val in matrix[pivot_row].iter_mut() {
+            *val /= &lead;
+        }
+        let pivot_vec = matrix[pivot_row].clone();
+        for (i, row) in matrix.iter_mut().enumerate() {
+            if i != pivot_row && row[col] != 0 {
+                let factor = row[col].clone();
+                for (val, pivot_val) in row.iter_mut().zip(&pivot_vec) {
+                    *val -= Rational::from(&factor * pivot_val);
+                }
+            }
+        }
+        pivot_row += 1;
+    }
+    matrix.truncate(pivot_row);
+    return matrix
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|x| {
+                    if *x.denom() == 1 {
+                        x.into_numer_denom().0
+                    } else {
+                        Integer::from(-1)
+                    }
+                })
+                .collect()
+        })
+        .collect();
+}
+
+/// First s power sums of the roots of a monic g mod m, via Newton's identities
+fn power_sums(g: &[Integer], s: usize, m: &Integer) -> Vec<Integer> {
+    let d = g.len() - 1;
+    // c[i] is the coefficient of x^(d-i)
+    let c = |i: usize| -> Integer {
+        if i > d {
+            Integer::from(0)
+        } else {
+            g[d - i].clone()
+        }
+    };
+    let mut sums: Vec<Integer> = Vec::new();
+    for k in 1..=s {
+        let mut val = Integer::from(k) * c(k);
+        for i in 1..k {
+            val += c(i) * &sums[k - i - 1];
+        }
+        val = -val % m;
+        if val < 0 {
+            val += m;
+        }
+        sums.push(val);
+    }
+    return sums;
+}
+
+/// All size-k subsets of 0..n in lexicographic order
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    let mut result = Vec::new();
+    let mut current: Vec<usize> = (0..k).collect();
+    if k > n {
+        return result;
+    }
+    loop {
+        result.push(current.clone());
+        let mut i = k;
+        while i > 0 && current[i - 1] == n - k + i - 1 {
+            i -= 1;
+        }
+        if i == 0 {
+            return result;
+        }
+        current[i - 1] += 1;
+        for j in i..k {
+            current[j] = current[j - 1] + 1;
+        }
+    }
+}
+
+/// Yun's algorithm over Z for a primitive f, returning (squarefree part, multiplicity)
+fn squarefree_decomposition(f: &[Integer]) -> Vec<(Vec<Integer>, u32)> {
+    let mut result = Vec::new();
+    let f_prime = derivative_z(f);
+    if f_prime.is_empty() {
+        return result;
+    }
+    let b = gcd_z(f, &f_prime);
+    let mut c = divide_exact_z(f, &b).unwrap();
+    let mut d = sub_z(&divide_exact_z(&f_prime, &b).unwrap(), &derivative_z(&c));
+    let mut i = 1;
+    while c.len() > 1 {
+        let a = gcd_z(&c, &d);
+        if a.len() > 1 {
+            result.push((a.clone(), i));
+        }
+        c = divide_exact_z(&c, &a).unwrap();
+        d = sub_z(&divide_exact_z(&d, &a).unwrap(), &derivative_z(&c));
+        i += 1;
+    }
+    return result;
+}
+
+/// Splits a monic squarefree f mod p into (product of all degree d factors, d)
+fn distinct_degree_factor(f: &[Integer], p: &Integer) -> Vec<(Vec<Integer>, usize)> {
+    let mut result = Vec::new();
+    let x = vec![Integer::from(0), Integer::from(1)];
+    let mut f = f.to_vec();
+    let mut h = x.clone();
+    let mut d = 1;
+    while f.len() > 2 * d {
+        h = reduce(&cryptlib::powmod(&h, p, &f, p), p);
+        let g = gcd_mod(&sub_mod(&h, &x, p), &f, p);
+        if g.len() > 1 {
+            f = divrem_mod(&f, &g, p).0;
+            h = divrem_mod(&h, &f, p).1;
+            result.push((g, d));
+        }
+        d += 1;
+    }
+    if f.len() > 1 {
+        let deg = f.len() - 1;
+        result.push((f, deg));
+    }
+    return result;
+}
+
+/// Splits a monic f mod p whose irreducible factors all have degree d
+fn equal_degree_factor(
+    f: &[Integer],
+    d: usize,
+    p: &Integer,
+    rand: &mut RandState,
+    factors: &mut Vec<Vec<Integer>>,
+) {
+    let n = f.len() - 1;
+    if n == d {
+        factors.push(f.to_vec());
+        return;
+    }
+    let exponent = (Integer::from(p.pow(d as u32)) - 1) / 2;
+    loop {
+        let a: Vec<Integer> = trim(
+            (0..n)
+                .map(|_| Integer::from(p.random_below_ref(rand)))
+                .collect(),
+        );
+        if a.len() < 2 {
+            continue;
+        }
+        let b = sub_mod(
+            &reduce(&cryptlib::powmod(&a, &exponent, f, p), p),
+            &[Integer::from(1)],
+            p,
+        );
+        let g = gcd_mod(&b, f, p);
+        if g.len() > 1 && g.len() < f.len() {
+            let h = divrem_mod(f, &g, p).0;
+            equal_degree_factor(&g, d, p, rand, factors);
+            equal_degree_factor(&h, d, p, rand, factors);
+            return;
+        }
+    }
+}
+
+/// Removes trailing zero coefficients, so the zero polynomial is empty
+fn trim(mut f: Vec<Integer>) -> Vec<Integer> {
+    while f.last().is_some_and(|x| *x == 0) {
+        f.pop();
+    }
+    return f;
+}
+
+fn symmetric_mod(x: &Integer, m: &Integer) -> Integer {
+    let mut r = Integer::from(x % m);
+    if r < 0 {
+        r += m;
+    }
+    if Integer::from(&r * 2) > *m {
+        r -= m;
+    }
+    return r;
+}
+
+fn reduce(f: &[Integer], m: &Integer) -> Vec<Integer> {
+    return trim(
+        f.iter()
+            .map(|x| {
+                let r = Integer::from(x % m);
+                if r < 0 {
+                    r + m
+                } else {
+                    r
+                }
+            })
+            .collect(),
+    );
+}
+
+fn add_mod(f: &[Integer], g: &[Integer], m: &Integer) -> Vec<Integer> {
+    let mut sum = vec![Integer::from(0); std::cmp::max(f.len(), g.len())];
+    for (i, val) in f.iter().enumerate() {
+        sum[i] += val;
+    }
+    for (i, val) in g.iter().enumerate() {
+        sum[i] += val;
+    }
+    return reduce(&sum, m);
+}
+
+fn sub_mod(f: &[Integer], g: &[Integer], m: &Integer) -> Vec<Integer> {
+    let neg_g: Vec<Integer> = g.iter().map(|x| Integer::from(-x)).collect();
+    return add_mod(f, &neg_g, m);
+}
+
+fn mul_mod(f: &[Integer], g: &[Integer], m: &Integer) -> Vec<Integer> {
+    if f.is_empty() || g.is_empty() {
+        return Vec::new();
+    }
+    return reduce(&cryptlib::multiply_poly_zn(f, g, m), m);
+}
+
+fn monic_mod(f: &[Integer], m: &Integer) -> Vec<Integer> {
+    let inv = cryptlib::find_inverse(&f[f.len() - 1], m);
+    return reduce(
+        &f.iter()
+            .map(|x| Integer::from(x * &inv))
+            .collect::<Vec<_>>(),
+        m,
+    );
+}
+
+/// Division with remainder mod m, the leading coefficient of g must be a unit mod m
+fn divrem_mod(f: &[Integer], g: &[Integer], m: &Integer) -> (Vec<Integer>, Vec<Integer>) {
+    let mut r = reduce(f, m);
+    let g = reduce(g, m);
+    if r.len() < g.len() {
+        return (Vec::new(), r);
+    }
+    let inv = cryptlib::find_inverse(&g[g.len() - 1], m);
+    let mut q = vec![Integer::from(0); r.len() - g.len() + 1];
+    while r.len() >= g.len() {
+        let shift = r.len() - g.len();
+        let t = Integer::from(&r[r.len() - 1] * &inv) % m;
+        for (i, val) in g.iter().enumerate() {
+            r[i + shift] -= Integer::from(val * &t);
+            r[i + shift] %= m;
+        }
+        q[shift] = t;
+        r = reduce(&r, m);
+    }
+    return (trim(q), r);
+}
+
+/// Monic gcd mod a prime p
+fn gcd_mod(f: &[Integer], g: &[Integer], p: &Integer) -> Vec<Integer> {
+    let mut a = reduce(f, p);
+    let mut b = reduce(g, p);
+    while !b.is_empty() {
+        let r = divrem_mod(&a, &b, p).1;
+        a = b;
+        b = r;
+    }
+    if a.is_empty() {
+        return a;
+    }
+    return monic_mod(&a, p);
+}
+
+/// Returns (d, s, t) with s f + t g = d = gcd(f, g) monic, mod a prime p
+fn ext_gcd_mod(
+    f: &[Integer],
+    g: &[Integer],
+    p: &Integer,
+) -> (Vec<Integer>, Vec<Integer>, Vec<Integer>) {
+    let (mut r0, mut r1) = (reduce(f, p), reduce(g, p));
+    let (mut s0, mut s1) = (vec![Integer::from(1)], Vec::new());
+    let (mut t0, mut t1) = (Vec::new(), vec![Integer::from(1)]);
+    while !r1.is_empty() {
+        let (q, r) = divrem_mod(&r0, &r1, p);
+        let s = sub_mod(&s0, &mul_mod(&q, &s1, p), p);
+        let t = sub_mod(&t0, &mul_mod(&q, &t1, p), p);
+        r0 = std::mem::replace(&mut r1, r);
+        s0 = std::mem::replace(&mut s1, s);
+        t0 = std::mem::replace(&mut t1, t);
+    }
+    let inv = cryptlib::find_inverse(&r0[r0.len() - 1], p);
+    let scale = |v: &Vec<Integer>| {
+        reduce(
+            &v.iter()
+                .map(|x| Integer::from(x * &inv))
+                .collect::<Vec<_>>(),
+            p,
+        )
+    };
+    return (scale(&r0), scale(&s0), scale(&t0));
+}
+
+fn content(f: &[Integer]) -> Integer {
+    let mut gcd = Integer::from(0);
+    for coef in f {
+        gcd = cryptlib::gcd(&gcd, coef);
+    }
+    return gcd;
+}
+
+fn primitive_part(f: &[Integer]) -> Vec<Integer> {
+    let cont = content(f);
+    if cont == 0 {
+        return Vec::new();
+    }
+    let mut f: Vec<Integer> = f
+        .iter()
+        .map(|x| Integer::from(x.div_exact_ref(&cont)))
+        .collect();
+    if f[f.len() - 1] < 0 {
+        for val in f.iter_mut() {
+            *val = Integer::from(-&*val);
+        }
+    }
+    return f;
+}
+
+fn sub_z(f: &[Integer], g: &[Integer]) -> Vec<Integer> {
+    let mut diff = vec![Integer::from(0); std::cmp::max(f.len(), g.len())];
+    for (i, val) in f.iter().enumerate() {
+        diff[i] += val;
+    }
+    for (i, val) in g.iter().enumerate() {
+        diff[i] -= val;
+    }
+    return trim(diff);
+}
+
+fn derivative_z(f: &[Integer]) -> Vec<Integer> {
+    return trim(
+        f.iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, x)| Integer::from(x * i as u32))
+            .collect(),
+    );
+}
+
+/// Primitive gcd over Z by the primitive polynomial remainder sequence
+fn gcd_z(f: &[Integer], g: &[Integer]) -> Vec<Integer> {
+    let mut a = primitive_part(f);
+    let mut b = primitive_part(g);
+    while !b.is_empty() {
+        let r = pseudo_remainder(&a, &b);
+        a = b;
+        b = primitive_part(&r);
+    }
+    return a;
+}
+
+fn pseudo_remainder(f: &[Integer], g: &[Integer]) -> Vec<Integer> {
+    let mut r = f.to_vec();
+    let lc = &g[g.len() - 1];
+    while r.len() >= g.len() {
+        let shift = r.len() - g.len();
+        let lead = r[r.len() - 1].clone();
+        for val in r.iter_mut() {
+            *val *= lc;
+        }
+        for (i, val) in g.iter().enumerate() {
+            r[i + shift] -= Integer::from(val * &lead);
+        }
+        r = trim(r);
+    }
+    return r;
+}
+
+/// Returns f / g if g divides f over Z
+fn divide_exact_z(f: &[Integer], g: &[Integer]) -> Option<Vec<Integer>> {
+    let mut r = trim(f.to_vec());
+    let g = trim(g.to_vec());
+    if r.len() < g.len() {
+        return if r.is_empty() { Some(Vec::new()) } else { None };
+    }
+    let lc = &g[g.len() - 1];
+    let mut q = vec![Integer::from(0); r.len() - g.len() + 1];
+    while r.len() >= g.len() {
+        let shift = r.len() - g.len();
+        if !r[r.len() - 1].is_divisible(lc) {
+            return None;
+        }
+        let t = Integer::from(r[r.len() - 1].div_exact_ref(lc));
+        for (i, val) in g.iter().enumerate() {
+            r[i + shift] -= Integer::from(val * &t);
+        }
+        q[shift] = t;
+        r = trim(r);
+    }
+    if !r.is_empty() {
+        return None;
+    }
+    return Some(q);
+}
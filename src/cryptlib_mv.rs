@@ -0,0 +1,809 @@
+use crate::{cryptlib, cryptlib_factor};
+use rug::{ops::Pow, Integer, Rational};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+/// Orders on monomials given as exponent vectors, with the first variable the most significant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MonomialOrder {
+    /// lexicographic on the exponents
+    Lex,
+    /// total degree, ties broken lexicographically
+    Grlex,
+    /// total degree, ties broken by the smaller exponent in the last differing variable
+    Grevlex,
+}
+
+impl MonomialOrder {
+    /// Compares two exponent vectors, Greater means a comes before b
+    pub fn compare(self, a: &[u32], b: &[u32]) -> Ordering {
+        let total = |m: &[u32]| m.iter().map(|e| *e as u64).sum::<u64>();
+        match self {
+            MonomialOrder::Lex => a.cmp(b),
+            MonomialOrder::Grlex => total(a).cmp(&total(b)).then_with(|| a.cmp(b)),
+            MonomialOrder::Grevlex => total(a).cmp(&total(b)).then_with(|| {
+                for (a_exp, b_exp) in a.iter().zip(b).rev() {
+                    if a_exp != b_exp {
+                        return b_exp.cmp(a_exp);
+                    }
+                }
+                Ordering::Equal
+            }),
+        }
+    }
+}
+
+/// Sparse polynomial in a fixed number of variables, storing only nonzero terms by exponent vector
+/// Operations take n like the rest of the crate: mod n when n > 0, over the integers otherwise
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolyMv {
+    vars: usize,
+    terms: BTreeMap<Vec<u32>, Integer>,
+}
+
+impl PolyMv {
+    /// The zero polynomial in the given number of variables
+    pub fn new(vars: usize) -> PolyMv {
+        return PolyMv {
+            vars,
+            terms: BTreeMap::new(),
+        };
+    }
+
+    pub fn constant(vars: usize, c: Integer) -> PolyMv {
+        return PolyMv::monomial(vec![0; vars], c);
+    }
+
+    /// The polynomial x_var
+    pub fn variable(vars: usize, var: usize) -> PolyMv {
+        assert!(var < vars, "variable {} out of {}", var, vars);
+        let mut exponents = vec![0; vars];
+        exponents[var] = 1;
+        return PolyMv::monomial(exponents, Integer::from(1));
+    }
+
+    pub fn monomial(exponents: Vec<u32>, c: Integer) -> PolyMv {
+        let mut f = PolyMv::new(exponents.len());
+        f.add_term(exponents, c);
+        return f;
+    }
+
+    /// Sums the given terms, which may repeat exponent vectors
+    pub fn from_terms<I>(vars: usize, terms: I) -> PolyMv
+    where
+        I: IntoIterator<Item = (Vec<u32>, Integer)>,
+    {
+        let mut f = PolyMv::new(vars);
+        for (exponents, c) in terms {
+            f.add_term(exponents, c);
+        }
+        return f;
+    }
+
+    /// Converts the bivariate layout of cryptlib_bv, f[i][j] the coefficient of x^i y^j, placing
+    /// x and y at the given variables
+    pub fn from_bv(f: &[Vec<Integer>], vars: usize, x_var: usize, y_var: usize) -> PolyMv {
+        assert!(
+            x_var != y_var && x_var < vars && y_var < vars,
+            "bad bivariate variables"
+        );
+        let mut result = PolyMv::new(vars);
+        for (i, row) in f.iter().enumerate() {
+            for (j, val) in row.iter().enumerate() {
+                let mut exponents = vec![0; vars];
+                exponents[x_var] = i as u32;
+                exponents[y_var] = j as u32;
+                result.add_term(exponents, val.clone());
+            }
+        }
+        return result;
+    }
+
+    /// Converts to the bivariate layout of cryptlib_bv, sized by the degrees in x and y
+    /// Panics if any other variable appears
+    pub fn to_bv(&self, x_var: usize, y_var: usize) -> Vec<Vec<Integer>> {
+        let dx = self.degree_in(x_var) as usize;
+        let dy = self.degree_in(y_var) as usize;
+        let mut result: Vec<Vec<Integer>> = (0..=dx)
+            .map(|_| (0..=dy).map(|_| Integer::from(0)).collect())
+            .collect();
+        for (exponents, c) in &self.terms {
+            let other = exponents
+                .iter()
+                .enumerate()
+                .any(|(var, exp)| var != x_var && var != y_var && *exp > 0);
+            assert!(!other, "polynomial is not bivariate in these variables");
+            result[exponents[x_var] as usize][exponents[y_var] as usize] = c.clone();
+        }
+        return result;
+    }
+
+    /// Coefficients in x_var from the constant term up, panics if any other variable appears
+    pub fn to_univariate(&self, var: usize) -> Vec<Integer> {
+        let mut result: Vec<Integer> = (0..=self.degree_in(var))
+            .map(|_| Integer::from(0))
+            .collect();
+        for (exponents, c) in &self.terms {
+            let other = exponents
+                .iter()
+                .enumerate()
+                .any(|(i, exp)| i != var && *exp > 0);
+            assert!(!other, "polynomial is not univariate in x{}", var);
+            result[exponents[var] as usize] = c.clone();
+        }
+        return result;
+    }
+
+    pub fn vars(&self) -> usize {
+        return self.vars;
+    }
+
+    pub fn is_zero(&self) -> bool {
+        return self.terms.is_empty();
+    }
+
+    /// True for nonzero constants, which make a system of equations inconsistent
+    pub fn is_constant(&self) -> bool {
+        return self.terms.len() == 1 && self.total_degree() == 0;
+    }
+
+    /// Number of nonzero terms
+    pub fn len(&self) -> usize {
+        return self.terms.len();
+    }
+
+    pub fn coefficient(&self, exponents: &[u32]) -> Integer {
+        return self.terms.get(exponents).cloned().unwrap_or_default();
+    }
+
+    /// Terms with nonzero coefficients in increasing lex order
+    pub fn terms(&self) -> impl Iterator<Item = (&Vec<u32>, &Integer)> {
+        return self.terms.iter();
+    }
+
+    /// Terms sorted from the largest monomial down in the given order
+    pub fn terms_ordered(&self, order: MonomialOrder) -> Vec<(&Vec<u32>, &Integer)> {
+        let mut terms: Vec<(&Vec<u32>, &Integer)> = self.terms.iter().collect();
+        terms.sort_by(|a, b| order.compare(b.0, a.0));
+        return terms;
+    }
+
+    /// Largest term in the given order, None for the zero polynomial
+    pub fn leading_term(&self, order: MonomialOrder) -> Option<(&Vec<u32>, &Integer)> {
+        return self.terms.iter().max_by(|a, b| order.compare(a.0, b.0));
+    }
+
+    pub fn total_degree(&self) -> u32 {
+        return self
+            .terms
+            .keys()
+            .map(|exponents| exponents.iter().sum())
+            .max()
+            .unwrap_or(0);
+    }
+
+    pub fn degree_in(&self, var: usize) -> u32 {
+        return self
+            .terms
+            .keys()
+            .map(|exponents| exponents[var])
+            .max()
+            .unwrap_or(0);
+    }
+
+    /// Reduces coefficients into [0, n) when n > 0, dropping terms that vanish
+    pub fn reduce(&self, n: &Integer) -> PolyMv {
+        let mut result = PolyMv::new(self.vars);
+        for (exponents, c) in &self.terms {
+            result.add_term_mod(exponents.clone(), c.clone(), n);
+        }
+        return result;
+    }
+
+    pub fn add(&self, other: &PolyMv, n: &Integer) -> PolyMv {
+        assert!(self.vars == other.vars, "different numbers of variables");
+        let mut result = self.reduce(n);
+        for (exponents, c) in &other.terms {
+            result.add_term_mod(exponents.clone(), c.clone(), n);
+        }
+        return result;
+    }
+
+    pub fn sub(&self, other: &PolyMv, n: &Integer) -> PolyMv {
+        return self.add(&other.scale(&Integer::from(-1), n), n);
+    }
+
+    pub fn scale(&self, c: &Integer, n: &Integer) -> PolyMv {
+        let mut result = PolyMv::new(self.vars);
+        for (exponents, val) in &self.terms {
+            result.add_term_mod(exponents.clone(), Integer::from(val * c), n);
+        }
+        return result;
+    }
+
+    /// Multiplies by the monomial c x^exponents
+    pub fn mul_term(&self, exponents: &[u32], c: &Integer, n: &Integer) -> PolyMv {
+        let mut result = PolyMv::new(self.vars);
+        for (term, val) in &self.terms {
+            let product: Vec<u32> = term.iter().zip(exponents).map(|(a, b)| a + b).collect();
+            result.add_term_mod(product, Integer::from(val * c), n);
+        }
+        return result;
+    }
+
+    pub fn mul(&self, other: &PolyMv, n: &Integer) -> PolyMv {
+        assert!(self.vars == other.vars, "different numbers of variables");
+        let mut result = PolyMv::new(self.vars);
+        for (exponents, c) in &other.terms {
+            for (term, val) in &self.terms {
+                let product: Vec<u32> = term.iter().zip(exponents).map(|(a, b)| a + b).collect();
+                result.add_term(product, Integer::from(val * c));
+            }
+        }
+        return result.reduce(n);
+    }
+
+    /// Returns self^e by square and multiply
+    pub fn exp(&self, e: &Integer, n: &Integer) -> PolyMv {
+        let mut result = PolyMv::constant(self.vars, Integer::from(1));
+        for i in (0..e.significant_bits()).rev() {
+            result = result.mul(&result, n);
+            if e.get_bit(i) {
+                result = result.mul(self, n);
+            }
+        }
+        return result;
+    }
+
+    pub fn eval(&self, point: &[Integer], n: &Integer) -> Integer {
+        assert!(
+            point.len() == self.vars,
+            "point has the wrong number of coordinates"
+        );
+        let mut sum = Integer::from(0);
+        for (exponents, c) in &self.terms {
+            let mut term = c.clone();
+            for (val, exp) in point.iter().zip(exponents) {
+                if *n > 0 {
+                    term *= Integer::from(val.pow_mod_ref(&Integer::from(*exp), n).unwrap());
+                    term %= n;
+                } else {
+                    term *= Integer::from(val.pow(*exp));
+                }
+            }
+            sum += term;
+        }
+        if *n > 0 {
+            sum %= n;
+            if sum < 0 {
+                sum += n;
+            }
+        }
+        return sum;
+    }
+
+    /// Sets x_var to value, the variable stays in the count but no longer appears
+    pub fn partial_eval(&self, var: usize, value: &Integer, n: &Integer) -> PolyMv {
+        let mut result = PolyMv::new(self.vars);
+        for (exponents, c) in &self.terms {
+            let power = if *n > 0 {
+                Integer::from(
+                    value
+                        .pow_mod_ref(&Integer::from(exponents[var]), n)
+                        .unwrap(),
+                )
+            } else {
+                Integer::from(value.pow(exponents[var]))
+            };
+            let mut reduced = exponents.clone();
+            reduced[var] = 0;
+            result.add_term_mod(reduced, power * c, n);
+        }
+        return result;
+    }
+
+    /// Replaces x_var by the polynomial g
+    pub fn substitute(&self, var: usize, g: &PolyMv, n: &Integer) -> PolyMv {
+        assert!(self.vars == g.vars, "different numbers of variables");
+        // the remaining terms grouped by their power of x_var, each multiplied by that power of g
+        let mut by_power: BTreeMap<u32, PolyMv> = BTreeMap::new();
+        for (exponents, c) in &self.terms {
+            let mut reduced = exponents.clone();
+            reduced[var] = 0;
+            by_power
+                .entry(exponents[var])
+                .or_insert_with(|| PolyMv::new(self.vars))
+                .add_term(reduced, c.clone());
+        }
+        let mut result = PolyMv::new(self.vars);
+        let mut g_power = PolyMv::constant(self.vars, Integer::from(1));
+        let mut power = 0;
+        for (target, part) in by_power {
+            while power < target {
+                g_power = g_power.mul(g, n);
+                power += 1;
+            }
+            result = result.add(&part.mul(&g_power, n), n);
+        }
+        return result;
+    }
+
+    fn add_term(&mut self, exponents: Vec<u32>, c: Integer) {
+        self.add_term_mod(exponents, c, &Integer::from(-1));
+    }
+
+    /// Adds c x^exponents, reducing the coefficient into [0, n) when n > 0
+    fn add_term_mod(&mut self, exponents: Vec<u32>, c: Integer, n: &Integer) {
+        assert!(
+            exponents.len() == self.vars,
+            "exponent vector has the wrong length"
+        );
+        if c == 0 {
+            return;
+        }
+        let entry = self.terms.entry(exponents.clone()).or_default();
+        *entry += c;
+        if *n > 0 {
+            *entry %= n;
+            if *entry < 0 {
+                *entry += n;
+            }
+        }
+        if *entry == 0 {
+            self.terms.remove(&exponents);
+        }
+    }
+}
+
+/// Prints terms in grevlex order, with variables x, y, z, or x0, x1, ... beyond three
+impl fmt::Display for PolyMv {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        let name = |var: usize| -> String {
+            if self.vars <= 3 {
+                ["x", "y", "z"][var].to_string()
+            } else {
+                format!("x{}", var)
+            }
+        };
+        for (i, (exponents, c)) in self
+            .terms_ordered(MonomialOrder::Grevlex)
+            .iter()
+            .enumerate()
+        {
+            let is_constant = exponents.iter().all(|exp| *exp == 0);
+            if i > 0 {
+                write!(f, " {} ", if **c < 0 { "-" } else { "+" })?;
+            } else if **c < 0 {
+                write!(f, "-")?;
+            }
+            let abs = Integer::from(c.abs_ref());
+            if abs != 1 || is_constant {
+                write!(f, "{}", abs)?;
+            }
+            let mut first = abs == 1;
+            for (var, exp) in exponents.iter().enumerate() {
+                if *exp == 0 {
+                    continue;
+                }
+                if !first {
+                    write!(f, "*")?;
+                }
+                first = false;
+                write!(f, "{}", name(var))?;
+                if *exp > 1 {
+                    write!(f, "^{}", exp)?;
+                }
+            }
+        }
+        return Ok(());
+    }
+}
+
+/// Reduced Groebner basis of the ideal generated by polys, by Buchberger's algorithm with the
+/// coprime and chain criteria, sorted by increasing leading monomial
+/// Works over GF(p) with monic polynomials when p > 0, and over Q otherwise, where each
+/// polynomial is kept as a primitive integer multiple with a positive leading coefficient
+pub fn groebner_basis(polys: &[PolyMv], order: MonomialOrder, p: &Integer) -> Vec<PolyMv> {
+    let mut basis: Vec<PolyMv> = polys
+        .iter()
+        .map(|f| normalize_groebner(f.reduce(p), order, p))
+        .filter(|f| !f.is_zero())
+        .collect();
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    for j in 0..basis.len() {
+        for i in 0..j {
+            pairs.push((i, j));
+        }
+    }
+
+    while !pairs.is_empty() {
+        // normal strategy, the pair with the smallest lcm of leading monomials first
+        let lcms: Vec<Vec<u32>> = pairs
+            .iter()
+            .map(|(i, j)| {
+                lcm_monomial(
+                    leading_monomial(&basis[*i], order),
+                    leading_monomial(&basis[*j], order),
+                )
+            })
+            .collect();
+        let next = (0..pairs.len())
+            .min_by(|a, b| order.compare(&lcms[*a], &lcms[*b]))
+            .unwrap();
+        let (i, j) = pairs.swap_remove(next);
+        let lcm = &lcms[next];
+
+        let lead_i = leading_monomial(&basis[i], order);
+        let lead_j = leading_monomial(&basis[j], order);
+        if lead_i.iter().zip(lead_j).all(|(a, b)| *a == 0 || *b == 0) {
+            continue;
+        }
+        let chain = (0..basis.len()).any(|k| {
+            k != i
+                && k != j
+                && divides(leading_monomial(&basis[k], order), lcm)
+                && !pairs.contains(&(i.min(k), i.max(k)))
+                && !pairs.contains(&(j.min(k), j.max(k)))
+        });
+        if chain {
+            continue;
+        }
+
+        let h = normal_form(
+            &s_polynomial(&basis[i], &basis[j], order, p),
+            &basis,
+            order,
+            p,
+        );
+        if h.is_zero() {
+            continue;
+        }
+        if h.is_constant() {
+            return vec![h];
+        }
+        for k in 0..basis.len() {
+            pairs.push((k, basis.len()));
+        }
+        basis.push(h);
+    }
+
+    // minimal basis, then reduce every element by the others
+    let mut minimal: Vec<PolyMv> = Vec::new();
+    for (i, f) in basis.iter().enumerate() {
+        let lead = leading_monomial(f, order);
+        let redundant = basis.iter().enumerate().any(|(k, g)| {
+            let g_lead = leading_monomial(g, order);
+            k != i && divides(g_lead, lead) && (g_lead != lead || k < i)
+        });
+        if !redundant {
+            minimal.push(f.clone());
+        }
+    }
+    let mut reduced = Vec::new();
+    for i in 0..minimal.len() {
+        let others: Vec<PolyMv> = minimal
+            .iter()
+            .enumerate()
+            .filter(|(k, _)| *k != i)
+            .map(|(_, g)| g.clone())
+            .collect();
+        reduced.push(normal_form(&minimal[i], &others, order, p));
+    }
+    reduced.sort_by(|a, b| order.compare(leading_monomial(a, order), leading_monomial(b, order)));
+    return reduced;
+}
+
+/// Fully reduces f by the polynomials in basis, normalized like groebner_basis
+pub fn normal_form(f: &PolyMv, basis: &[PolyMv], order: MonomialOrder, p: &Integer) -> PolyMv {
+    let mut h = f.reduce(p);
+    let mut remainder = PolyMv::new(f.vars);
+    while let Some((lead, c)) = h.leading_term(order).map(|(m, c)| (m.clone(), c.clone())) {
+        let divisor = basis
+            .iter()
+            .find(|g| !g.is_zero() && divides(leading_monomial(g, order), &lead));
+        let g = match divisor {
+            Some(g) => g,
+            None => {
+                h.terms.remove(&lead);
+                remainder.add_term(lead, c);
+                continue;
+            }
+        };
+        let (g_lead, g_c) = g.leading_term(order).unwrap();
+        let shift: Vec<u32> = lead.iter().zip(g_lead).map(|(a, b)| a - b).collect();
+        if *p > 0 {
+            let factor = -c * cryptlib::find_inverse(g_c, p);
+            h = h.add(&g.mul_term(&shift, &factor, p), p);
+        } else {
+            // scale h by lc(g) / gcd instead of dividing, so everything stays in the integers
+            let common = cryptlib::gcd(&c, g_c);
+            let a = Integer::from(g_c / &common);
+            let b = -c / &common;
+            h = h.scale(&a, p).add(&g.mul_term(&shift, &b, p), p);
+            remainder = remainder.scale(&a, p);
+            let mut content = Integer::from(0);
+            for val in h.terms.values().chain(remainder.terms.values()) {
+                content = cryptlib::gcd(&content, val);
+            }
+            if content > 1 {
+                h = divide_exact(&h, &content);
+                remainder = divide_exact(&remainder, &content);
+            }
+        }
+    }
+    return normalize_groebner(remainder, order, p);
+}
+
+/// Common integer roots of polys, solving a lex Groebner basis from the last variable up
+/// Only variables fixed by a univariate polynomial at their turn are found, so systems with
+/// infinitely many solutions give none
+pub fn common_integer_roots(polys: &[PolyMv]) -> Vec<Vec<Integer>> {
+    let mut solutions = Vec::new();
+    if polys.is_empty() {
+        return solutions;
+    }
+    let vars = polys[0].vars;
+    let mut point: Vec<Integer> = (0..vars).map(|_| Integer::from(0)).collect();
+    solve_from_last(polys.to_vec(), vars, &mut point, &mut solutions);
+    solutions.sort();
+    return solutions;
+}
+
+fn solve_from_last(
+    polys: Vec<PolyMv>,
+    unknowns: usize,
+    point: &mut Vec<Integer>,
+    solutions: &mut Vec<Vec<Integer>>,
+) {
+    let over_q = Integer::from(-1);
+    let basis = groebner_basis(&polys, MonomialOrder::Lex, &over_q);
+    if basis.iter().any(|g| g.is_constant()) {
+        return;
+    }
+    if unknowns == 0 {
+        solutions.push(point.clone());
+        return;
+    }
+    let var = unknowns - 1;
+    let univariate = basis.iter().find(|g| {
+        g.terms.keys().all(|exponents| {
+            exponents
+                .iter()
+                .enumerate()
+                .all(|(i, exp)| i == var || *exp == 0)
+        })
+    });
+    let univariate = match univariate {
+        Some(g) if g.degree_in(var) > 0 => g,
+        _ => return,
+    };
+    for root in cryptlib_factor::integer_roots(&univariate.to_univariate(var)) {
+        point[var] = root.clone();
+        let specialized: Vec<PolyMv> = basis
+            .iter()
+            .map(|g| g.partial_eval(var, &root, &over_q))
+            .filter(|g| !g.is_zero())
+            .collect();
+        solve_from_last(specialized, unknowns - 1, point, solutions);
+    }
+}
+
+/// S-polynomial lc(g) m_f f - lc(f) m_g g, with the monomials lifting both leads to their lcm
+fn s_polynomial(f: &PolyMv, g: &PolyMv, order: MonomialOrder, p: &Integer) -> PolyMv {
+    let (f_lead, f_c) = f.leading_term(order).unwrap();
+    let (g_lead, g_c) = g.leading_term(order).unwrap();
+    let lcm = lcm_monomial(f_lead, g_lead);
+    let f_shift: Vec<u32> = lcm.iter().zip(f_lead).map(|(a, b)| a - b).collect();
+    let g_shift: Vec<u32> = lcm.iter().zip(g_lead).map(|(a, b)| a - b).collect();
+    let common = if *p > 0 {
+        Integer::from(1)
+    } else {
+        cryptlib::gcd(f_c, g_c)
+    };
+    let f_part = f.mul_term(&f_shift, &Integer::from(g_c / &common), p);
+    let g_part = g.mul_term(&g_shift, &Integer::from(f_c / &common), p);
+    return f_part.sub(&g_part, p);
+}
+
+/// Monic mod p, or primitive with a positive leading coefficient over Q
+fn normalize_groebner(f: PolyMv, order: MonomialOrder, p: &Integer) -> PolyMv {
+    let c = match f.leading_term(order) {
+        Some((_, c)) => c.clone(),
+        None => return f,
+    };
+    if *p > 0 {
+        return f.scale(&cryptlib::find_inverse(&c, p), p);
+    }
+    let mut content = Integer::from(0);
+    for val in f.terms.values() {
+        content = cryptlib::gcd(&content, val);
+    }
+    if c < 0 {
+        content = -content;
+    }
+    return divide_exact(&f, &content);
+}
+
+fn divide_exact(f: &PolyMv, d: &Integer) -> PolyMv {
+    let mut result = f.clone();
+    for val in result.terms.values_mut() {
+        val.div_exact_mut(d);
+    }
+    return result;
+}
+
+fn leading_monomial(f: &PolyMv, order: MonomialOrder) -> &Vec<u32> {
+    return f.leading_term(order).unwrap().0;
+}
+
+fn lcm_monomial(a: &[u32], b: &[u32]) -> Vec<u32> {
+    return a.iter().zip(b).map(|(x, y)| *x.max(y)).collect();
+}
+
+/// True when the monomial a divides b
+fn divides(a: &[u32], b: &[u32]) -> bool {
+    return a.iter().zip(b).all(|(x, y)| x <= y);
+}
+
+/// Resultant of f and g with respect to x_var over the integers, a polynomial in the other
+/// variables. Evaluates one remaining variable at a time and interpolates, so it is exact
+pub fn resultant_mv(f: &PolyMv, g: &PolyMv, var: usize) -> PolyMv {
+    assert!(f.vars == g.vars, "different numbers of variables");
+    return resultant_formal(
+        f,
+        g,
+        var,
+        f.degree_in(var) as usize,
+        g.degree_in(var) as usize,
+    );
+}
+
+/// resultant_mv taking f and g with formal degrees in x_var, so that specializations where a
+/// leading coefficient vanishes still agree with the Sylvester determinant
+fn resultant_formal(
+    f: &PolyMv,
+    g: &PolyMv,
+    var: usize,
+    f_degree: usize,
+    g_degree: usize,
+) -> PolyMv {
+    let over_z = Integer::from(-1);
+    let other = (0..f.vars).find(|y| *y != var && (f.degree_in(*y) > 0 || g.degree_in(*y) > 0));
+    let y = match other {
+        Some(y) => y,
+        None => {
+            let mut f_coeffs = f.to_univariate(var);
+            let mut g_coeffs = g.to_univariate(var);
+            f_coeffs.resize(f_degree + 1, Integer::from(0));
+            g_coeffs.resize(g_degree + 1, Integer::from(0));
+            let result = cryptlib::resultant_univariate(&f_coeffs, &g_coeffs);
+            return PolyMv::constant(f.vars, result);
+        }
+    };
+
+    // each Sylvester entry has degree at most deg_y in y, which bounds the determinant
+    let bound = f_degree * g.degree_in(y) as usize + g_degree * f.degree_in(y) as usize;
+    let points: Vec<Rational> = (0..=bound).map(Rational::from).collect();
+    let values: Vec<PolyMv> = (0..=bound)
+        .map(|t| {
+            let t = Integer::from(t);
+            let f_t = f.partial_eval(y, &t, &over_z);
+            let g_t = g.partial_eval(y, &t, &over_z);
+            resultant_formal(&f_t, &g_t, var, f_degree, g_degree)
+        })
+        .collect();
+
+    let monomials: BTreeSet<Vec<u32>> = values
+        .iter()
+        .flat_map(|v| v.terms.keys().cloned())
+        .collect();
+    let mut result = PolyMv::new(f.vars);
+    for monomial in monomials {
+        let coefficients: Vec<Rational> = values
+            .iter()
+            .map(|v| Rational::from(v.coefficient(&monomial)))
+            .collect();
+        for (k, c) in cryptlib::interpolate_q(&points, &coefficients)
+            .into_iter()
+            .enumerate()
+        {
+            assert!(*c.denom() == 1, "resultant interpolated to a fraction");
+            let mut exponents = monomial.clone();
+            exponents[y] = k as u32;
+            result.add_term(exponents, c.numer().clone());
+        }
+    }
+    return result;
+}
+
+/// Common integer roots of polys, as from the first few LLL reduced polynomials of a Coppersmith
+/// lattice. Eliminates x_0, x_1, ... in turn with pairwise resultants, then solves the last
+/// variable and back-substitutes level by level, keeping points where every level vanishes
+pub fn roots_by_resultants(polys: &[PolyMv]) -> Vec<Vec<Integer>> {
+    let mut solutions = Vec::new();
+    if polys.is_empty() {
+        return solutions;
+    }
+    let vars = polys[0].vars;
+    if vars == 0 {
+        // constants, the empty point is a root when all of them are zero
+        if polys.iter().all(|f| f.is_zero()) {
+            solutions.push(Vec::new());
+        }
+        return solutions;
+    }
+    let mut levels: Vec<Vec<PolyMv>> =
+        vec![polys.iter().filter(|f| !f.is_zero()).cloned().collect()];
+    for var in 0..(vars - 1) {
+        let level = &levels[levels.len() - 1];
+        let (with_var, mut next): (Vec<PolyMv>, Vec<PolyMv>) =
+            level.iter().cloned().partition(|f| f.degree_in(var) > 0);
+        // pairs in order until there are as many resultants as polynomials lost to the elimination
+        let wanted = with_var.len().saturating_sub(1);
+        let mut found = 0;
+        'pairs: for i in 0..with_var.len() {
+            for j in (i + 1)..with_var.len() {
+                if found == wanted {
+                    break 'pairs;
+                }
+                let resultant = resultant_mv(&with_var[i], &with_var[j], var);
+                if !resultant.is_zero() {
+                    next.push(resultant);
+                    found += 1;
+                }
+            }
+        }
+        levels.push(next);
+    }
+    let mut point: Vec<Integer> = (0..vars).map(|_| Integer::from(0)).collect();
+    back_substitute(&levels, vars - 1, &mut point, &mut solutions);
+    solutions.sort();
+    return solutions;
+}
+
+/// Solves levels[var] for x_var with x_{var+1}, ... already fixed in point, then moves up a level
+fn back_substitute(
+    levels: &[Vec<PolyMv>],
+    var: usize,
+    point: &mut Vec<Integer>,
+    solutions: &mut Vec<Vec<Integer>>,
+) {
+    let over_z = Integer::from(-1);
+    let mut univariate = Vec::new();
+    for f in &levels[var] {
+        let mut specialized = f.clone();
+        for (fixed, value) in point.iter().enumerate().skip(var + 1) {
+            specialized = specialized.partial_eval(fixed, value, &over_z);
+        }
+        if specialized.is_constant() {
+            return;
+        }
+        if !specialized.is_zero() {
+            univariate.push(specialized.to_univariate(var));
+        }
+    }
+    // nothing left to pin down this variable
+    if univariate.is_empty() {
+        return;
+    }
+    for root in cryptlib_factor::integer_roots(&univariate[0]) {
+        let vanishes = univariate[1..]
+            .iter()
+            .all(|f| cryptlib::eval_poly(&root, f, &over_z) == 0);
+        if !vanishes {
+            continue;
+        }
+        point[var] = root;
+        if var == 0 {
+            solutions.push(point.clone());
+        } else {
+            back_substitute(levels, var - 1, point, solutions);
+        }
+    }
+}
@@ -0,0 +1,554 @@
+use crate::cryptlib;
+use crate::cryptlib_rsa::RsaPrivateKey;
+use rug::{integer::Order, rand::RandState, Float, Integer};
+
+/// Answers whether a ciphertext decrypts to plaintext passing the padding check the oracle leaks,
+/// the only thing a padding oracle attack gets to see
+pub trait PaddingOracle {
+    fn query(&mut self, c: &Integer) -> bool;
+}
+
+/// Big-endian bytes of x, left padded with zeros to length k
+pub fn to_bytes(x: &Integer, k: usize) -> Vec<u8> {
+    let digits = x.to_digits::<u8>(Order::Msf);
+    let mut bytes = vec![0; k.saturating_sub(digits.len())];
+    bytes.extend(digits);
+    return bytes;
+}
+
+/// Byte length of N
+pub fn modulus_len(n: &Integer) -> usize {
+    return n.significant_bits().div_ceil(8) as usize;
+}
+
+/// 00 02 PS 00 msg for k-byte N, PS at least 8 random nonzero bytes (PKCS#1 v1.5, type 2)
+pub fn pkcs1_v15_pad(msg: &[u8], k: usize, rand: &mut RandState) -> Option<Vec<u8>> {
+    if msg.len() + 11 > k {
+        return None;
+    }
+    let mut em = vec![0x00, 0x02];
+    for _ in 0..(k - msg.len() - 3) {
+        em.push((rand.below(255) + 1) as u8);
+    }
+    em.push(0x00);
+    em.extend_from_slice(msg);
+    return Some(em);
+}
+
+/// The message in em, if em is 00 02, at least 8 nonzero bytes, 00 and the message
+pub fn pkcs1_v15_unpad(em: &[u8]) -> Option<Vec<u8>> {
+    if em.len() < 11 || em[0] != 0x00 || em[1] != 0x02 {
+        return None;
+    }
+    let separator = em[2..].iter().position(|byte| *byte == 0)? + 2;
+    if separator < 10 {
+        return None;
+    }
+    return Some(em[(separator + 1)..].to_vec());
+}
+
+/// (pad(msg))^e mod N
+pub fn pkcs1_v15_encrypt(
+    n: &Integer,
+    e: &Integer,
+    msg: &[u8],
+    rand: &mut RandState,
+) -> Option<Integer> {
+    let em = pkcs1_v15_pad(msg, modulus_len(n), rand)?;
+    return Some(cryptlib::fast_power(
+        &Integer::from_digits(&em, Order::Msf),
+        e,
+        n,
+    ));
+}
+
+pub fn pkcs1_v15_decrypt(key: &RsaPrivateKey, c: &Integer) -> Option<Vec<u8>> {
+    let em = to_bytes(&key.decrypt_crt(c), modulus_len(&key.n));
+    return pkcs1_v15_unpad(&em);
+}
+
+/// A PKCS#1 v1.5 padding oracle holding the private key
+/// A strict oracle accepts only what pkcs1_v15_unpad does, a lenient one every plaintext starting
+/// 00 02, which answers yes more often and so leaks more per query
+pub struct Pkcs1Oracle {
+    pub key: RsaPrivateKey,
+    pub strict: bool,
+    pub queries: usize,
+}
+
+impl PaddingOracle for Pkcs1Oracle {
+    fn query(&mut self, c: &Integer) -> bool {
+        self.queries += 1;
+        let em = to_bytes(&self.key.decrypt_crt(c), modulus_len(&self.key.n));
+        if self.strict {
+            return pkcs1_v15_unpad(&em).is_some();
+        }
+        return em[0] == 0x00 && em[1] == 0x02;
+    }
+}
+
+/// Progress of Bleichenbacher's attack after each step 3
+#[derive(Debug, Clone)]
+pub struct BleichenbacherStep {
+    pub i: usize,
+    /// oracle queries made so far, blinding included
+    pub queries: usize,
+    pub intervals: usize,
+    /// log2 of the total width of the intervals that may still hold m s0
+    pub log2_width: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct BleichenbacherReport {
+    /// the padded plaintext as an integer
+    pub m: Integer,
+    pub queries: usize,
+    pub steps: Vec<BleichenbacherStep>,
+}
+
+/// Recovers m = c^d mod N from a PKCS#1 v1.5 padding oracle (Bleichenbacher 1998)
+/// Every s with c s^e conforming puts m s0 s mod N in [2B, 3B) for B = 2^(8(k - 2)), which
+/// narrows the intervals holding m s0 until one value is left (steps 1 to 4)
+pub fn bleichenbacher(
+    n: &Integer,
+    e: &Integer,
+    c: &Integer,
+    oracle: &mut dyn PaddingOracle,
+) -> Option<BleichenbacherReport> {
+    let k = modulus_len(n);
+    let cap_b = Integer::from(1) << (8 * (k as u32 - 2));
+    let two_b = Integer::from(&cap_b * 2);
+    let three_b = Integer::from(&cap_b * 3);
+    let mut queries = 0;
+    let mut conforming = |s: &Integer, c0: &Integer, queries: &mut usize| {
+        *queries += 1;
+        let c_s = cryptlib::fast_power(s, e, n) * c0 % n;
+        return oracle.query(&c_s);
+    };
+
+    // step 1: blind c until it is conforming
+    let mut rand = RandState::new();
+    let mut s0 = Integer::from(1);
+    while !conforming(&s0, c, &mut queries) {
+        s0 = Integer::from(n.random_below_ref(&mut rand));
+    }
+    let c0 = cryptlib::fast_power(&s0, e, n) * c % n;
+
+    let mut intervals = vec![(two_b.clone(), Integer::from(&three_b - 1))];
+    let mut s = Integer::from(0);
+    let mut steps = Vec::new();
+    for i in 1.. {
+        if i == 1 {
+            // step 2a: the smallest s >= N / 3B
+            s = div_ceil(n, &three_b);
+            while !conforming(&s, &c0, &mut queries) {
+                s += 1;
+            }
+        } else if intervals.len() > 1 {
+            // step 2b: the next conforming s
+            s += 1;
+            while !conforming(&s, &c0, &mut queries) {
+                s += 1;
+            }
+        } else {
+            // step 2c: r and s about doubling the chance of a hit each time
+            let (a, b) = &intervals[0];
+            let mut r = div_ceil(&((Integer::from(b * &s) - &two_b) * 2u32), n);
+            'search: loop {
+                let low = div_ceil(&(Integer::from(&r * n) + &two_b), b);
+                let high = div_ceil(&(Integer::from(&r * n) + &three_b), a);
+                s = low;
+                while s < high {
+                    if conforming(&s, &c0, &mut queries) {
+                        break 'search;
+                    }
+                    s += 1;
+                }
+                r += 1;
+            }
+        }
+
+        // step 3: keep the parts of each interval where m s0 s mod N can be conforming
+        let mut narrowed: Vec<(Integer, Integer)> = Vec::new();
+        for (a, b) in &intervals {
+            let mut r = div_ceil(&(Integer::from(a * &s) - &three_b + 1), n);
+            let r_end = (Integer::from(b * &s) - &two_b) / n;
+            while r <= r_end {
+                let low = div_ceil(&(Integer::from(&r * n) + &two_b), &s).max(a.clone());
+                let high = (Integer::from(&r * n) + &three_b - 1u32) / &s;
+                let high = high.min(b.clone());
+                if low <= high {
+                    insert_interval(&mut narrowed, low, high);
+                }
+                r += 1;
+            }
+        }
+        if narrowed.is_empty() {
+            return None;
+        }
+        intervals = narrowed;
+        let width: Integer = intervals
+            .iter()
+            .map(|(a, b)| Integer::from(b - a) + 1)
+            .sum();
+        steps.push(BleichenbacherStep {
+            i,
+            queries,
+            intervals: intervals.len(),
+            log2_width: Float::with_val(64, &width).log2().to_f64(),
+        });
+
+        // step 4: one value left is m s0
+        if intervals.len() == 1 && intervals[0].0 == intervals[0].1 {
+            let m = &intervals[0].0 * cryptlib::find_inverse(&s0, n) % n;
+            return Some(BleichenbacherReport { m, queries, steps });
+        }
+    }
+    return None;
+}
+
+/// ceil(a / b) for b > 0
+fn div_ceil(a: &Integer, b: &Integer) -> Integer {
+    let (q, r) = Integer::from(a).div_rem_floor(b.clone());
+    if r == 0 {
+        return q;
+    }
+    return q + 1;
+}
+
+/// Adds [low, high] to a list of disjoint intervals, merging it with any it overlaps
+fn insert_interval(intervals: &mut Vec<(Integer, Integer)>, mut low: Integer, mut high: Integer) {
+    intervals.retain(|(a, b)| {
+        if *a <= high && low <= *b {
+            low = std::cmp::min(low.clone(), a.clone());
+            high = std::cmp::max(high.clone(), b.clone());
+            return false;
+        }
+        return true;
+    });
+    intervals.push((low, high));
+}
+
+/// Hash used by OAEP and MGF1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hash {
+    Sha1,
+    Sha256,
+}
+
+impl Hash {
+    pub fn output_len(&self) -> usize {
+        return match self {
+            Hash::Sha1 => 20,
+            Hash::Sha256 => 32,
+        };
+    }
+
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        return match self {
+            Hash::Sha1 => sha1(data),
+            Hash::Sha256 => sha256(data),
+        };
+    }
+}
+
+/// data, 0x80, zeros and the bit length, to a multiple of 64 bytes (SHA-1 and SHA-256)
+fn md_pad(data: &[u8]) -> Vec<u8> {
+    let mut padded = data.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+    return padded;
+}
+
+fn sha1(data: &[u8]) -> Vec<u8> {
+    let mut h: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+    for block in md_pad(data).chunks(64) {
+        let mut w = [0u32; 80];
+        for t in 0..80 {
+            w[t] = if t < 16 {
+                u32::from_be_bytes(block[(4 * t)..(4 * t + 4)].try_into().unwrap())
+            } else {
+                (w[t - 3] ^ w[t - 8] ^ w[t - 14] ^ w[t - 16]).rotate_left(1)
+            };
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (t, w_t) in w.iter().enumerate() {
+            let (f, k) = match t / 20 {
+                0 => ((b & c) | (!b & d), 0x5a827999),
+                1 => (b ^ c ^ d, 0x6ed9eba1),
+                2 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+                _ => (b ^ c ^ d, 0xca62c1d6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*w_t);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (h_i, x) in h.iter_mut().zip([a, b, c, d, e]) {
+            *h_i = h_i.wrapping_add(x);
+        }
+    }
+    return h.iter().flat_map(|h_i| h_i.to_be_bytes()).collect();
+}
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+fn sha256(data: &[u8]) -> Vec<u8> {
+    let mut h: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+    for block in md_pad(data).chunks(64) {
+        let mut w = [0u32; 64];
+        for t in 0..64 {
+            w[t] = if t < 16 {
+                u32::from_be_bytes(block[(4 * t)..(4 * t + 4)].try_into().unwrap())
+            } else {
+                let s0 = w[t - 15].rotate_right(7) ^ w[t - 15].rotate_right(18) ^ (w[t - 15] >> 3);
+                let s1 = w[t - 2].rotate_right(17) ^ w[t - 2].rotate_right(19) ^ (w[t - 2] >> 10);
+                w[t - 16]
+                    .wrapping_add(s0)
+                    .wrapping_add(w[t - 7])
+                    .wrapping_add(s1)
+            };
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+        for (k_t, w_t) in SHA256_K.iter().zip(w) {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let temp1 = hh
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(*k_t)
+                .wrapping_add(w_t);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let temp2 = s0.wrapping_add(maj);
+            hh = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(temp1);
+            d = c;
+            c = b;
+            b = a;
+            a = temp1.wrapping_add(temp2);
+        }
+        for (h_i, x) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+            *h_i = h_i.wrapping_add(x);
+        }
+    }
+    return h.iter().flat_map(|h_i| h_i.to_be_bytes()).collect();
+}
+
+/// Mask of len bytes, hash(seed || 0) || hash(seed || 1) || ... truncated (MGF1)
+pub fn mgf1(hash: Hash, seed: &[u8], len: usize) -> Vec<u8> {
+    let mut mask = Vec::new();
+    let mut counter: u32 = 0;
+    while mask.len() < len {
+        let mut block = seed.to_vec();
+        block.extend_from_slice(&counter.to_be_bytes());
+        mask.extend(hash.digest(&block));
+        counter += 1;
+    }
+    mask.truncate(len);
+    return mask;
+}
+
+fn xor_in_place(data: &mut [u8], mask: &[u8]) {
+    for (byte, mask_byte) in data.iter_mut().zip(mask) {
+        *byte ^= mask_byte;
+    }
+}
+
+/// Why oaep_decode rejected an encoded message
+/// A decryptor that lets the two be told apart is Manger's oracle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OaepError {
+    /// the first byte is not zero
+    LeadingByte,
+    /// the label hash, the zeros or the 01 separator are wrong
+    Padding,
+}
+
+/// 00 || maskedSeed || maskedDB for k-byte N, DB = hash(label) || 00 .. 00 || 01 || msg (RSA-OAEP)
+pub fn oaep_encode(
+    msg: &[u8],
+    label: &[u8],
+    k: usize,
+    hash: Hash,
+    rand: &mut RandState,
+) -> Option<Vec<u8>> {
+    let h_len = hash.output_len();
+    if msg.len() + 2 * h_len + 2 > k {
+        return None;
+    }
+    let mut db = hash.digest(label);
+    db.resize(k - h_len - msg.len() - 2, 0);
+    db.push(0x01);
+    db.extend_from_slice(msg);
+    let mut seed: Vec<u8> = (0..h_len).map(|_| rand.bits(8) as u8).collect();
+    xor_in_place(&mut db, &mgf1(hash, &seed, k - h_len - 1));
+    xor_in_place(&mut seed, &mgf1(hash, &db, h_len));
+
+    let mut em = vec![0x00];
+    em.extend(seed);
+    em.extend(db);
+    return Some(em);
+}
+
+pub fn oaep_decode(em: &[u8], label: &[u8], hash: Hash) -> Result<Vec<u8>, OaepError> {
+    let h_len = hash.output_len();
+    if em.len() < 2 * h_len + 2 {
+        return Err(OaepError::Padding);
+    }
+    if em[0] != 0x00 {
+        return Err(OaepError::LeadingByte);
+    }
+    let mut seed = em[1..(h_len + 1)].to_vec();
+    let mut db = em[(h_len + 1)..].to_vec();
+    xor_in_place(&mut seed, &mgf1(hash, &db, h_len));
+    let db_mask = mgf1(hash, &seed, db.len());
+    xor_in_place(&mut db, &db_mask);
+
+    if db[..h_len] != hash.digest(label)[..] {
+        return Err(OaepError::Padding);
+    }
+    let separator = match db[h_len..].iter().position(|byte| *byte != 0) {
+        Some(i) if db[h_len + i] == 0x01 => h_len + i,
+        _ => return Err(OaepError::Padding),
+    };
+    return Ok(db[(separator + 1)..].to_vec());
+}
+
+pub fn oaep_encrypt(
+    n: &Integer,
+    e: &Integer,
+    msg: &[u8],
+    label: &[u8],
+    hash: Hash,
+    rand: &mut RandState,
+) -> Option<Integer> {
+    let em = oaep_encode(msg, label, modulus_len(n), hash, rand)?;
+    return Some(cryptlib::fast_power(
+        &Integer::from_digits(&em, Order::Msf),
+        e,
+        n,
+    ));
+}
+
+pub fn oaep_decrypt(
+    key: &RsaPrivateKey,
+    c: &Integer,
+    label: &[u8],
+    hash: Hash,
+) -> Result<Vec<u8>, OaepError> {
+    let em = to_bytes(&key.decrypt_crt(c), modulus_len(&key.n));
+    return oaep_decode(&em, label, hash);
+}
+
+/// An RSA-OAEP decryptor holding the private key that reports a nonzero first byte differently
+/// from other padding errors, so query answers whether c^d mod N < B = 2^(8(k - 1))
+pub struct OaepOracle {
+    pub key: RsaPrivateKey,
+    pub label: Vec<u8>,
+    pub hash: Hash,
+    pub queries: usize,
+}
+
+impl PaddingOracle for OaepOracle {
+    fn query(&mut self, c: &Integer) -> bool {
+        self.queries += 1;
+        return oaep_decrypt(&self.key, c, &self.label, self.hash) != Err(OaepError::LeadingByte);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MangerReport {
+    /// the encoded message as an integer
+    pub m: Integer,
+    pub queries: usize,
+    /// oracle queries made by each of steps 1, 2 and 3
+    pub step_queries: [usize; 3],
+}
+
+/// Recovers m = c^d mod N < B = 2^(8(k - 1)) from an oracle telling whether (f^e c)^d mod N < B
+/// (Manger 2001)
+/// Steps 1 and 2 find f1, f2 with f1 m / 2 in [B / 2, B) and f2 m in [N, N + B), then step 3
+/// halves an interval holding m with each query, so about log2(N) queries in all
+pub fn manger(
+    n: &Integer,
+    e: &Integer,
+    c: &Integer,
+    oracle: &mut dyn PaddingOracle,
+) -> Option<MangerReport> {
+    let k = modulus_len(n);
+    let cap_b = Integer::from(1) << (8 * (k as u32 - 1));
+    if Integer::from(&cap_b * 2) >= *n {
+        return None;
+    }
+    let mut step_queries = [0; 3];
+    let mut below_b = |f: &Integer, step: usize| {
+        step_queries[step] += 1;
+        let c_f = cryptlib::fast_power(f, e, n) * c % n;
+        return oracle.query(&c_f);
+    };
+
+    // step 1: double f1 until f1 m >= B
+    let mut f1 = Integer::from(2);
+    while below_b(&f1, 0) {
+        f1 *= 2;
+        if f1 > *n {
+            return None;
+        }
+    }
+    let half_f1 = Integer::from(&f1 / 2);
+
+    // step 2: f2 m starts in [N / 2, N + B) and steps by less than B until it wraps below B
+    let mut f2 = Integer::from(n + &cap_b) / &cap_b * &half_f1;
+    while !below_b(&f2, 1) {
+        f2 += &half_f1;
+        if f2 > Integer::from(n * 2) {
+            return None;
+        }
+    }
+
+    // step 3: each f3 splits [m_min, m_max] at a multiple of N
+    let mut m_min = div_ceil(n, &f2);
+    let mut m_max = Integer::from(n + &cap_b) / &f2;
+    while m_min < m_max {
+        let f_tmp = Integer::from(&cap_b * 2) / Integer::from(&m_max - &m_min);
+        let i = f_tmp * &m_min / n;
+        let i_n = i * n;
+        let f3 = div_ceil(&i_n, &m_min);
+        let boundary = i_n + &cap_b;
+        if below_b(&f3, 2) {
+            m_max = boundary / f3;
+        } else {
+            m_min = div_ceil(&boundary, &f3);
+        }
+    }
+    return Some(MangerReport {
+        m: m_min,
+        queries: step_queries.iter().sum(),
+        step_queries,
+    });
+}
@@ -0,0 +1,346 @@
+use crate::cryptlib_rsa::RsaPrivateKey;
+use crate::{cryptlib, cryptlib_bv, cryptlib_factor};
+use rug::{integer::IsPrime, ops::Pow, Float, Integer};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Offsets from n/2 tried for the bit length of p, nearest first
+const L_OFFSETS: [i32; 17] = [0, 1, -1, 2, -2, 3, -3, 4, -4, 5, -5, 6, -6, 7, -7, 8, -8];
+
+/// Recovers the key from the low bits of d, d0 = d mod 2^bits, when e is small (Boneh, Durfee
+/// and Frankel)
+/// ed - k(N - p - q + 1) = 1 with 0 < k < e, so multiplying by p gives
+/// k p^2 + (e d0 - k(N + 1) - 1) p + kN = 0 mod 2^bits, and every solution is a candidate for
+/// p mod 2^bits; bits a little over n/4 then factor N (see factor_from_low_bits), at the cost of
+/// trying each k < e
+pub fn partial_key_d_lsb(
+    n: &Integer,
+    e: &Integer,
+    d0: &Integer,
+    bits: u32,
+) -> Option<RsaPrivateKey> {
+    let modulus = Integer::from(1) << bits;
+    let ed0 = Integer::from(e * d0) % &modulus;
+
+    let mut p_candidates: Vec<Integer> = Vec::new();
+    let mut k = Integer::from(1);
+    while k < *e {
+        let a = Integer::from(&k % &modulus);
+        let b = (&ed0 - Integer::from(&k * n) - &k - 1) % &modulus;
+        let b = if b < 0 { b + &modulus } else { b };
+        let c = Integer::from(&k * n) % &modulus;
+        for candidate in cryptlib::solve_quadratic(&a, &b, &c, bits) {
+            // p0 and q0 = N / p0 both solve it, so only the smaller is kept
+            if !candidate.is_odd() {
+                continue;
+            }
+            let complement = n * cryptlib::find_inverse(&candidate, &modulus) % &modulus;
+            let p_candidate = std::cmp::min(candidate, complement);
+            if !p_candidates.contains(&p_candidate) {
+                p_candidates.push(p_candidate);
+            }
+        }
+        k += 1;
+    }
+
+    let (p, q) = search_low_bits(n, &p_candidates, &modulus)?;
+    return Some(key_from_factors(n, e, &p, &q));
+}
+
+/// Recovers the key from the high bits of d, d = 2^unknown_bits d_msb + x with x < 2^unknown_bits,
+/// when e is a prime between N^(1/4) and N^(1/2) (Boneh, Durfee and Frankel)
+/// k = (ed - 1) / φ(N) is within a few of (e d~ - 1) / (N + 1 - 2 N^(1/2)) when unknown_bits is
+/// below about log2(N / e), and then k φ(N) = -1 mod e gives p + q = N + 1 + k^-1 mod e, so
+/// p mod e is a root of z^2 - (p + q) z + N mod e, which factors N once e > N^(1/4)
+pub fn partial_key_d_msb(
+    n: &Integer,
+    e: &Integer,
+    d_msb: &Integer,
+    unknown_bits: u32,
+) -> Option<RsaPrivateKey> {
+    if e.is_probably_prime(40) == IsPrime::No {
+        return None;
+    }
+    let d_approx = Integer::from(d_msb << unknown_bits) + (Integer::from(1) << unknown_bits) / 2;
+    // φ(N) is about N + 1 - 2 N^(1/2) for balanced p, q
+    let phi_approx = Integer::from(n + 1) - Integer::from(n.sqrt_ref()) * 2;
+    let k_approx: Integer =
+        (Integer::from(e * &d_approx) - 1 + Integer::from(&phi_approx / 2)) / &phi_approx;
+    // the unknown bits move k by up to e 2^unknown_bits / N, and unbalanced factors by up to 3
+    let spread: Integer = Integer::from(e << unknown_bits) / n + 3;
+    let spread = spread.to_u64()?;
+
+    // nearest k first; p mod e and q mod e are both roots, and either one factors N
+    let mut ks = vec![k_approx.clone()];
+    for offset in 1..=spread {
+        ks.push(Integer::from(&k_approx + offset));
+        ks.push(Integer::from(&k_approx - offset));
+    }
+    let mut p_candidates = Vec::new();
+    for k in ks {
+        if k < 1 || k >= *e {
+            continue;
+        }
+        let k_inverse = match cryptlib::unit_inverse(&k, e) {
+            Ok(inverse) => inverse,
+            Err(_) => continue,
+        };
+        let s = (Integer::from(n + 1) + k_inverse) % e;
+        if let Some(p0) = roots_quadratic_mod_prime(&s, n, e).into_iter().next() {
+            p_candidates.push(p0);
+        }
+    }
+    let (p, q) = search_low_bits(n, &p_candidates, e)?;
+    return Some(key_from_factors(n, e, &p, &q));
+}
+
+/// Factors N from the low bits of p, p0 = p mod 2^bits, with bits a little over n/4
+pub fn partial_key_p_lsb(n: &Integer, p0: &Integer, bits: u32) -> Option<(Integer, Integer)> {
+    let modulus = Integer::from(1) << bits;
+    return search_low_bits(n, &[Integer::from(p0 % &modulus)], &modulus);
+}
+
+/// Factors N from the high bits of p, p = 2^unknown_bits p_msb + x with x < 2^unknown_bits, when
+/// unknown_bits is a little under n/4
+/// q = q~ + y for q~ = N / (p~ + 2^unknown_bits), so (x, y) is a small root of
+/// (p~ + x)(q~ + y) - N = xy + p~ y + q~ x + p~ q~ - N over Z
+pub fn partial_key_p_msb(
+    n: &Integer,
+    p_msb: &Integer,
+    unknown_bits: u32,
+) -> Option<(Integer, Integer)> {
+    let p_low = Integer::from(p_msb << unknown_bits);
+    let p_high = &p_low + (Integer::from(1) << unknown_bits);
+    let q_low = Integer::from(n / &p_high);
+    let q_high = Integer::from(n / &p_low) + 1;
+
+    let f = vec![
+        vec![Integer::from(&p_low * &q_low) - n, p_low.clone()],
+        vec![q_low.clone(), Integer::from(1)],
+    ];
+    let cap_x = Integer::from(1) << unknown_bits;
+    let cap_y = Integer::from(&q_high - &q_low);
+    let (x0, y0) = cryptlib_bv::coppersmith_bv(&f, &cap_x, &cap_y, 1)?;
+    let p = p_low + x0;
+    let q = q_low + y0;
+    if Integer::from(&p * &q) != *n {
+        return None;
+    }
+    return Some(if p > q { (p, q) } else { (q, p) });
+}
+
+/// The fewest known bits of a prime p ~ N^(1/2) that factor N: the unknown part has to stay
+/// below N^(1/4), so a quarter of the bits of N
+pub fn min_known_bits(n: &Integer) -> u32 {
+    return n.significant_bits().div_ceil(4);
+}
+
+/// Factors N from the bits of p set in mask, known = p & mask, when the unknown bits are one
+/// window (high, low or in the middle) of fewer than about n/4 bits
+/// p has max(bits of mask, n/2) bits; with the window at bits a..a + w, p = known + 2^a x for
+/// x < 2^w, a root mod p of the monic x + known 2^(-a) mod N (see coppersmith_divisor)
+pub fn factor_with_known_bits(
+    n: &Integer,
+    known: &Integer,
+    mask: &Integer,
+) -> Option<(Integer, Integer)> {
+    let p_bits = std::cmp::max(mask.significant_bits(), n.significant_bits().div_ceil(2));
+    let unknown: Vec<u32> = (0..p_bits).filter(|i| !mask.get_bit(*i)).collect();
+    let (low, high) = (*unknown.first()?, *unknown.last()?);
+    if (high - low + 1) as usize != unknown.len() {
+        return None;
+    }
+    let known = Integer::from(known & mask);
+
+    let shift_inverse = cryptlib::unit_inverse(&(Integer::from(1) << low), n).ok()?;
+    let f = vec![known.clone() * shift_inverse % n, Integer::from(1)];
+    let log2_n = Float::with_val(64, n).log2().to_f64();
+    let beta = (p_bits - 1) as f64 / log2_n;
+    let x = coppersmith_divisor(&f, n, beta, unknown.len() as u32)?;
+
+    let p = known + (x << low);
+    let q = Integer::from(n / &p);
+    if p <= 1 || Integer::from(&p * &q) != *n {
+        return None;
+    }
+    return Some(if p > q { (p, q) } else { (q, p) });
+}
+
+/// Finds x0 < 2^root_bits with f(x0) = 0 mod b for an unknown divisor b >= N^β of N, f monic
+/// of degree 1 (Howgrave-Graham, May)
+/// The lattice of N^(m - i) f^i for i < m and x^j f^m for j < m has every row vanishing at x0
+/// mod b^m, so a short enough vector vanishes over Z; m is the smallest up to 16 meeting
+/// det(L)^(1/w) 2^((w - 1)/4) < N^(βm) / sqrt(w)
+pub fn coppersmith_divisor(
+    f: &Vec<Integer>,
+    n: &Integer,
+    beta: f64,
+    root_bits: u32,
+) -> Option<Integer> {
+    let log2_n = Float::with_val(64, n).log2().to_f64();
+    let m = (1..=16u32).find(|&m| {
+        let w = 2 * m;
+        let log2_det =
+            (m * (m + 1) / 2) as f64 * log2_n + (w * (w - 1) / 2) as f64 * root_bits as f64;
+        log2_det / w as f64 + (w - 1) as f64 / 4.0
+            < beta * m as f64 * log2_n - 0.5 * (w as f64).log2()
+    })?;
+
+    let cap_x = Integer::from(1) << root_bits;
+    let w = (2 * m) as usize;
+    let mut basis = Vec::new();
+    for i in 0..m {
+        let mut row = cryptlib::exp_poly(f, &Integer::from(i));
+        for coef in row.iter_mut() {
+            *coef *= Integer::from(n.pow(m - i));
+        }
+        basis.push(row);
+    }
+    let f_m = cryptlib::exp_poly(f, &Integer::from(m));
+    for j in 0..m as usize {
+        let mut row = vec![Integer::from(0); j];
+        row.extend(f_m.iter().cloned());
+        basis.push(row);
+    }
+    for row in basis.iter_mut() {
+        row.resize(w, Integer::from(0));
+        for (i, coef) in row.iter_mut().enumerate() {
+            *coef <<= root_bits * i as u32;
+        }
+    }
+
+    let (reduced, _min_idx) = cryptlib::lll(&basis);
+    for v in reduced.iter().take(2) {
+        let h: Vec<Integer> = v
+            .iter()
+            .enumerate()
+            .map(|(i, coef)| Integer::from(coef >> (root_bits * i as u32)))
+            .collect();
+        if h.iter().all(|coef| *coef == 0) {
+            continue;
+        }
+        for x0 in cryptlib_factor::integer_roots(&h) {
+            if x0 >= 0 && x0 < cap_x && cryptlib::gcd(&cryptlib::eval_poly(&x0, f, n), n) != 1 {
+                return Some(x0);
+            }
+        }
+    }
+    return None;
+}
+
+/// Factors N from p0 = p mod M when M is a little over N^(1/4), for any M coprime to N
+/// With q0 = N / p0 mod M, p = M x + p0 and q = M y + q0, (x, y) is a small root of
+/// M xy + p0 y + q0 x + (p0 q0 - N) / M over Z; the bounds X, Y depend on the bit length l of p,
+/// which is tried around n/2
+pub fn factor_from_low_bits(
+    n: &Integer,
+    p0: &Integer,
+    modulus: &Integer,
+) -> Option<(Integer, Integer)> {
+    return search_low_bits(n, std::slice::from_ref(p0), modulus);
+}
+
+/// Runs search on every task across threads and returns the first success in task order with
+/// its index, so the result is the same as searching sequentially whatever the thread count
+/// Tasks are handed out in order, and once one succeeds the tasks after it are never started
+/// Tasks already running are not interrupted, so a success waits for the slowest of them, such
+/// as a full LLL reduction inside coppersmith_bv, and search must not print if its output is to
+/// stay readable
+pub fn parallel_search<T, R, F>(tasks: &[T], threads: usize, search: F) -> Option<(usize, R)>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> Option<R> + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let first_found = AtomicUsize::new(usize::MAX);
+    let found: Mutex<Vec<(usize, R)>> = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                if i >= tasks.len() || i > first_found.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Some(result) = search(&tasks[i]) {
+                    first_found.fetch_min(i, Ordering::SeqCst);
+                    found.lock().unwrap().push((i, result));
+                }
+            });
+        }
+    });
+    return found
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .min_by_key(|(i, _)| *i);
+}
+
+/// Tries every (bit length of p, candidate p mod M) pair in parallel, bit lengths nearest n/2
+/// first
+fn search_low_bits(
+    n: &Integer,
+    p_candidates: &[Integer],
+    modulus: &Integer,
+) -> Option<(Integer, Integer)> {
+    let n_len = n.significant_bits() as i32;
+    let tasks: Vec<(u32, &Integer)> = L_OFFSETS
+        .iter()
+        .flat_map(|l_offset| {
+            p_candidates
+                .iter()
+                .map(move |p0| ((n_len / 2 + l_offset) as u32, p0))
+        })
+        .collect();
+    let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+    let (_i, factors) = parallel_search(&tasks, threads, |(l, p0)| {
+        factor_from_low_bits_l(n, p0, modulus, *l)
+    })?;
+    return Some(factors);
+}
+
+/// factor_from_low_bits for p of bit length l, so x < 2^l / M and y < N / (2^(l - 1) M)
+fn factor_from_low_bits_l(
+    n: &Integer,
+    p0: &Integer,
+    modulus: &Integer,
+    l: u32,
+) -> Option<(Integer, Integer)> {
+    let q0 = n * cryptlib::unit_inverse(p0, modulus).ok()? % modulus;
+    let cap_x = Integer::from(2).pow(l) / modulus + 1;
+    let cap_y = n / (Integer::from(2).pow(l - 1) * modulus) + 1;
+
+    // f[i][j] is the coefficient of x^i y^j
+    let f = vec![
+        vec![(Integer::from(p0 * &q0) - n) / modulus, p0.clone()],
+        vec![q0.clone(), modulus.clone()],
+    ];
+    let (x0, y0) = cryptlib_bv::coppersmith_bv(&f, &cap_x, &cap_y, 1)?;
+    let p = modulus * x0 + p0;
+    let q = modulus * y0 + &q0;
+    if Integer::from(&p * &q) != *n {
+        return None;
+    }
+    return Some(if p > q { (p, q) } else { (q, p) });
+}
+
+/// Roots of z^2 - s z + N mod a prime r
+fn roots_quadratic_mod_prime(s: &Integer, n: &Integer, r: &Integer) -> Vec<Integer> {
+    let disc = (Integer::from(s.square_ref()) - Integer::from(n * 4)) % r;
+    if disc == 0 {
+        return vec![s * cryptlib::find_inverse(&Integer::from(2), r) % r];
+    }
+    let f = [Integer::from(n % r), Integer::from(r - s), Integer::from(1)];
+    return cryptlib_factor::factor_poly_zp(&f, r)
+        .into_iter()
+        .filter(|factor| factor.len() == 2)
+        .map(|factor| Integer::from(r - &factor[0]) % r)
+        .collect();
+}
+
+fn key_from_factors(n: &Integer, e: &Integer, p: &Integer, q: &Integer) -> RsaPrivateKey {
+    let phi = Integer::from(p - 1) * Integer::from(q - 1);
+    let d = cryptlib::find_inverse(e, &phi);
+    return RsaPrivateKey::from_factors(n, e, &d, p, q);
+}
@@ -0,0 +1,843 @@
+use crate::{cryptlib, cryptlib_bv, cryptlib_factor};
+use rug::{integer::Order, ops::Pow, rand::RandState, Float, Integer};
+use std::collections::BTreeMap;
+
+/// A factored RSA key with the values used for CRT decryption
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RsaPrivateKey {
+    pub n: Integer,
+    pub e: Integer,
+    pub d: Integer,
+    /// p > q
+    pub p: Integer,
+    pub q: Integer,
+    /// d mod (p - 1)
+    pub dp: Integer,
+    /// d mod (q - 1)
+    pub dq: Integer,
+    /// q^-1 mod p
+    pub q_inv: Integer,
+}
+
+impl RsaPrivateKey {
+    /// Fills in the CRT values from the factors
+    pub fn from_factors(
+        n: &Integer,
+        e: &Integer,
+        d: &Integer,
+        p: &Integer,
+        q: &Integer,
+    ) -> RsaPrivateKey {
+        let (p, q) = if p > q {
+            (p.clone(), q.clone())
+        } else {
+            (q.clone(), p.clone())
+        };
+        let dp = d % Integer::from(&p - 1);
+        let dq = d % Integer::from(&q - 1);
+        let q_inv = cryptlib::find_inverse(&q, &p);
+        return RsaPrivateKey {
+            n: n.clone(),
+            e: e.clone(),
+            d: d.clone(),
+            p,
+            q,
+            dp,
+            dq,
+            q_inv,
+        };
+    }
+
+    /// c^d mod N from c^dp mod p and c^dq mod q (Garner)
+    pub fn decrypt_crt(&self, c: &Integer) -> Integer {
+        return self.exponentiate_crt(c, CrtFault::None);
+    }
+
+    fn exponentiate_crt(&self, x: &Integer, fault: CrtFault) -> Integer {
+        let mut s_p = cryptlib::fast_power(x, &self.dp, &self.p);
+        let mut s_q = cryptlib::fast_power(x, &self.dq, &self.q);
+        match fault {
+            CrtFault::None => {}
+            CrtFault::FlipSp(bit) => {
+                s_p.toggle_bit(bit);
+            }
+            CrtFault::FlipSq(bit) => {
+                s_q.toggle_bit(bit);
+            }
+        }
+        // s = s_q + q h with h = q^-1 (s_p - s_q) mod p
+        let mut h = Integer::from(&s_p - &s_q) * &self.q_inv % &self.p;
+        if h < 0 {
+            h += &self.p;
+        }
+        return s_q + h * &self.q;
+    }
+}
+
+/// A fault injected into one half of a CRT exponentiation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrtFault {
+    None,
+    /// flips this bit of s_p = m^dp mod p
+    FlipSp(u32),
+    /// flips this bit of s_q = m^dq mod q
+    FlipSq(u32),
+}
+
+/// RSA-CRT signing with a fault injected into every signature
+#[derive(Debug, Clone)]
+pub struct CrtSigner {
+    pub key: RsaPrivateKey,
+    pub fault: CrtFault,
+    /// checks s^e = m before releasing s, the usual countermeasure
+    pub verify_before_release: bool,
+}
+
+impl CrtSigner {
+    /// s = m^d mod N, or None if verify_before_release caught a faulty signature
+    pub fn sign(&self, m: &Integer) -> Option<Integer> {
+        let s = self.key.exponentiate_crt(m, self.fault);
+        if self.verify_before_release
+            && cryptlib::fast_power(&s, &self.key.e, &self.key.n) != Integer::from(m % &self.key.n)
+        {
+            return None;
+        }
+        return Some(s);
+    }
+}
+
+/// One encryption c = f(m)^e mod N of a padded message in a broadcast
+#[derive(Debug, Clone)]
+pub struct BroadcastInstance {
+    pub n: Integer,
+    pub e: Integer,
+    pub c: Integer,
+    /// padding polynomial, [0, 1] when m is sent as is
+    pub f: Vec<Integer>,
+}
+
+/// Why franklin_reiter could not recover the messages
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FranklinReiterError {
+    /// gcd(f(x)^e - c1, x^e - c2) has this degree instead of 1, so m2 is not determined by it
+    /// (0 when the relation does not hold)
+    NonLinearGcd(usize),
+    /// a non-unit factored N, but e is not invertible mod φ(N)
+    Factor(Integer),
+}
+
+/// Part of a stereotyped message, with parts and bytes in reading order, so the message is
+/// big-endian as in Integer::from_digits(bytes, Order::Msf)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplatePart {
+    Known(Vec<u8>),
+    /// unknown value below 2^bits, taking up bits / 8 bytes rounded up
+    Unknown(u32),
+}
+
+/// Why stereotyped_message_attack could not recover the message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StereotypedMessageError {
+    /// the template has this many unknown parts instead of one
+    UnknownParts(usize),
+    /// the unknown part is too long for Coppersmith's method with this e
+    OutOfBound,
+    /// Coppersmith's method found no root below 2^bits
+    NoRoot,
+}
+
+/// Which Boneh-Durfee lattice to reduce
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BonehDurfeeLattice {
+    /// every x-shift and y-shift with t = (1 - 2δ)m / 2, square and triangular, tending to
+    /// d < N^0.284 as m grows
+    Full,
+    /// t = (1 - 2δ)m, keeping only the y-shifts whose diagonal is at most e^m, tending to
+    /// d < N^0.292 as m grows
+    Sublattice,
+}
+
+/// Recovers (d, p, q) from (N, e) when d < N^δ (Boneh-Durfee)
+/// ed = 1 + k(N + 1 - (p + q)) makes (k, -(p + q)) a root of f(x, y) = 1 + x(N + 1 + y) mod e
+/// with |k| < N^δ and |p + q| < 3 N^(1/2), which is found from the shifts x^i f^k e^(m-k) and
+/// y^j f^k e^(m-k); larger m gets closer to the bound at the cost of lattice dimension
+/// The m small enough to reduce in reasonable time stay well below either bound: for a 256 bit
+/// N, m = 4 reaches about d < N^0.26, while m = 5 and 6 still miss d ≈ N^0.27 and m = 6 takes
+/// about two minutes
+pub fn boneh_durfee(
+    n: &Integer,
+    e: &Integer,
+    delta: f64,
+    m: usize,
+    lattice: BonehDurfeeLattice,
+) -> Option<(Integer, Integer, Integer)> {
+    let debug = false;
+
+    let tau = match lattice {
+        BonehDurfeeLattice::Full => (1.0 - 2.0 * delta) / 2.0,
+        BonehDurfeeLattice::Sublattice => 1.0 - 2.0 * delta,
+    };
+    let t = (tau * m as f64).round() as usize;
+
+    let cap_x = Float::with_val(n.significant_bits() + 64, n)
+        .pow(delta)
+        .ceil()
+        .to_integer()
+        .unwrap();
+    let cap_y: Integer = Integer::from(n.sqrt_ref()) * 3;
+    let f = vec![
+        vec![Integer::from(1)],
+        vec![Integer::from(n + 1), Integer::from(1)],
+    ];
+
+    let mut f_pows = vec![vec![vec![Integer::from(1)]]];
+    for k in 1..=m {
+        f_pows.push(cryptlib_bv::multiply_poly_bv(
+            &f_pows[k - 1],
+            &f,
+            &Integer::from(-1),
+        ));
+    }
+
+    // shifts and their leading monomials x^a y^b, ordered so the full lattice is triangular
+    let e_m = e.clone().pow(m as u32);
+    let mut shifts: Vec<Vec<Vec<Integer>>> = Vec::new();
+    let mut leading: Vec<(usize, usize)> = Vec::new();
+    for (k, f_pow) in f_pows.iter().enumerate() {
+        for i in 0..=(m - k) {
+            shifts.push(shift_bv(f_pow, e, m - k, i, 0));
+            leading.push((i + k, k));
+        }
+    }
+    for j in 1..=t {
+        for (k, f_pow) in f_pows.iter().enumerate() {
+            let diagonal = e.clone().pow((m - k) as u32)
+                * cap_x.clone().pow(k as u32)
+                * cap_y.clone().pow((k + j) as u32);
+            if lattice == BonehDurfeeLattice::Sublattice && diagonal > e_m {
+                continue;
+            }
+            shifts.push(shift_bv(f_pow, e, m - k, 0, j));
+            leading.push((k, k + j));
+        }
+    }
+
+    // columns are every monomial any shift uses
+    let mut columns: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    for monomial in &leading {
+        let next = columns.len();
+        columns.entry(*monomial).or_insert(next);
+    }
+    for shift in &shifts {
+        for (a, row) in shift.iter().enumerate() {
+            for (b, coef) in row.iter().enumerate() {
+                if *coef != 0 {
+                    let next = columns.len();
+                    columns.entry((a, b)).or_insert(next);
+                }
+            }
+        }
+    }
+
+    let basis: Vec<Vec<Integer>> = shifts
+        .iter()
+        .map(|shift| {
+            let mut row = vec![Integer::from(0); columns.len()];
+            for (a, coefs) in shift.iter().enumerate() {
+                for (b, coef) in coefs.iter().enumerate() {
+                    if *coef != 0 {
+                        row[columns[&(a, b)]] =
+                            coef * cap_x.clone().pow(a as u32) * cap_y.clone().pow(b as u32);
+                    }
+                }
+            }
+            row
+        })
+        .collect();
+    if debug {
+        println!(
+            "t = {}, {} shifts over {} monomials",
+            t,
+            basis.len(),
+            columns.len()
+        );
+    }
+
+    let (reduced, _min_idx) = cryptlib::lll(&basis);
+    if debug {
+        let bound = Float::with_val(64, &e_m).log2().to_f64() - 0.5 * (basis.len() as f64).log2();
+        for v in reduced.iter().take(6) {
+            let norm: Integer = v.iter().map(|c| Integer::from(c * c)).sum();
+            println!(
+                "log2 |v| {:.1} vs bound {:.1}",
+                Float::with_val(64, &norm).log2().to_f64() / 2.0,
+                bound
+            );
+        }
+    }
+
+    // undo the X, Y scaling to get polynomials h(x, y) with h(k, -(p + q)) = 0 over Z
+    let (max_a, max_b) = columns
+        .keys()
+        .fold((0, 0), |(a, b), (x, y)| (a.max(*x), b.max(*y)));
+    let polys: Vec<Vec<Vec<Integer>>> = reduced
+        .iter()
+        .map(|v| {
+            let mut h = vec![vec![Integer::from(0); max_b + 1]; max_a + 1];
+            for ((a, b), column) in &columns {
+                h[*a][*b] =
+                    Integer::from(v[*column].div_exact_ref(
+                        &(cap_x.clone().pow(*a as u32) * cap_y.clone().pow(*b as u32)),
+                    ));
+            }
+            h
+        })
+        .collect();
+
+    // the shortest vectors are the likeliest to vanish over Z; pairs sharing a factor are skipped
+    let tries = std::cmp::min(polys.len(), 4);
+    for i in 0..tries {
+        for j in (i + 1)..tries {
+            let res =
+                cryptlib::resultant_interpolate(&polys[i], &polys[j], &Integer::from(-1)).unwrap();
+            if res.iter().all(|coef| *coef == 0) {
+                continue;
+            }
+            for y in cryptlib_factor::integer_roots(&res) {
+                if debug {
+                    println!("h{}, h{}: y = {}", i, j, y);
+                }
+                if let Some((p, q)) = factor_from_sum(n, &-y) {
+                    let phi = n.clone() - &p - &q + 1;
+                    let d = cryptlib::find_inverse(e, &phi);
+                    return Some((d, p, q));
+                }
+            }
+        }
+    }
+    return None;
+}
+
+/// x^i y^j e^power f
+fn shift_bv(
+    f: &[Vec<Integer>],
+    e: &Integer,
+    power: usize,
+    i: usize,
+    j: usize,
+) -> Vec<Vec<Integer>> {
+    let scale = e.clone().pow(power as u32);
+    let mut shift = vec![vec![Integer::from(0); f[f.len() - 1].len() + j]; f.len() + i];
+    for (a, row) in f.iter().enumerate() {
+        for (b, coef) in row.iter().enumerate() {
+            shift[a + i][b + j] = Integer::from(coef * &scale);
+        }
+    }
+    return shift;
+}
+
+/// Recovers (d, p, q) from (N, e) when d < N^(1/4) / 3 (Wiener)
+/// ed - kφ(N) = 1 makes k/d a convergent of e/N, and each convergent is tested for a φ(N)
+/// that factors N
+pub fn wiener(n: &Integer, e: &Integer) -> Option<(Integer, Integer, Integer)> {
+    return wiener_convergents(n, e, n);
+}
+
+/// Wiener's attack against e/(N + 1 - 2 sqrt(N)) instead of e/N (de Weger)
+/// φ(N) = N + 1 - 2 sqrt(N) - (sqrt(p) - sqrt(q))^2, so when |p - q| = N^β this is a closer
+/// approximation and d < N^(3/4 - β) is enough
+pub fn wiener_de_weger(n: &Integer, e: &Integer) -> Option<(Integer, Integer, Integer)> {
+    let mut root = Integer::from(n.sqrt_ref());
+    if Integer::from(root.square_ref()) < *n {
+        root += 1;
+    }
+    let approximation = Integer::from(n + 1) - root * 2;
+    return wiener_convergents(n, e, &approximation);
+}
+
+fn wiener_convergents(
+    n: &Integer,
+    e: &Integer,
+    denominator: &Integer,
+) -> Option<(Integer, Integer, Integer)> {
+    let quotients = cryptlib::continued_fraction(e, denominator);
+    for (k, d) in cryptlib::convergents(&quotients) {
+        if k == 0 {
+            continue;
+        }
+        // φ(N) = (ed - 1) / k, and then p + q = N + 1 - φ(N)
+        let ed_minus_one: Integer = Integer::from(e * &d) - 1;
+        let (phi, rem) = ed_minus_one.div_rem(k);
+        if rem != 0 {
+            continue;
+        }
+        if let Some((p, q)) = factor_from_sum(n, &(Integer::from(n + 1) - phi)) {
+            return Some((d, p, q));
+        }
+    }
+    return None;
+}
+
+/// Recovers m from encryptions of f_i(m) under pairwise coprime N_i (Hastad)
+/// Each g_i = f_i^e_i - c_i is made monic mod N_i and shifted by x^(𝛿 - 𝛿_i) to the largest degree
+/// 𝛿, then the coefficients are combined by CRT into g with g(m) = 0 mod prod N_i, which
+/// Coppersmith's method solves with parameters m, epsilon_denom when m < (prod N_i)^(1/𝛿)
+/// Without padding, the instances sharing an e are combined to m^e mod prod N_i first, which is
+/// m^e over Z once there are enough of them
+pub fn hastad_broadcast(
+    instances: &[BroadcastInstance],
+    m: u32,
+    epsilon_denom: u32,
+) -> Option<Integer> {
+    for (i, a) in instances.iter().enumerate() {
+        if instances[(i + 1)..]
+            .iter()
+            .any(|b| cryptlib::gcd(&a.n, &b.n) != 1)
+        {
+            return None;
+        }
+    }
+
+    let identity = [Integer::from(0), Integer::from(1)];
+    if instances
+        .iter()
+        .all(|instance| trim_poly(&instance.f) == identity)
+    {
+        if let Some(msg) = hastad_unpadded(instances) {
+            return Some(msg);
+        }
+    }
+
+    let mut gs: Vec<Vec<Integer>> = Vec::new();
+    for instance in instances {
+        let mut g = cryptlib::exp_poly_zn(trim_poly(&instance.f), &instance.e, &instance.n);
+        g[0] -= &instance.c;
+        g[0] %= &instance.n;
+        let lead = g[g.len() - 1].clone();
+        if g.len() == 1 || cryptlib::gcd(&lead, &instance.n) != 1 {
+            return None;
+        }
+        let inverse = cryptlib::find_inverse(&lead, &instance.n);
+        for coef in g.iter_mut() {
+            *coef = Integer::from(&*coef * &inverse) % &instance.n;
+        }
+        gs.push(g);
+    }
+    let len = gs.iter().map(|g| g.len()).max()?;
+    for g in gs.iter_mut() {
+        let shift = len - g.len();
+        g.splice(0..0, vec![Integer::from(0); shift]);
+    }
+
+    let moduli: Vec<&Integer> = instances.iter().map(|instance| &instance.n).collect();
+    let n = moduli.iter().fold(Integer::from(1), |acc, n_i| acc * *n_i);
+    let g: Vec<Integer> = (0..len)
+        .map(|j| cryptlib::crt(gs.iter().map(|g| &g[j]), moduli.iter().copied()))
+        .collect();
+    return cryptlib::coppersmith(&g, &n, m, epsilon_denom);
+}
+
+/// Integer e-th root of the CRT combination of m^e mod N_i, trying the smallest e first
+fn hastad_unpadded(instances: &[BroadcastInstance]) -> Option<Integer> {
+    let mut by_exponent: BTreeMap<&Integer, Vec<&BroadcastInstance>> = BTreeMap::new();
+    for instance in instances {
+        by_exponent.entry(&instance.e).or_default().push(instance);
+    }
+    for (e, group) in by_exponent {
+        let m_e = cryptlib::crt(
+            group.iter().map(|instance| &instance.c),
+            group.iter().map(|instance| &instance.n),
+        );
+        let (msg, rem) = m_e.root_rem(Integer::new(), e.to_u32()?);
+        if rem != 0 {
+            continue;
+        }
+        if instances
+            .iter()
+            .all(|instance| cryptlib::fast_power(&msg, &instance.e, &instance.n) == instance.c)
+        {
+            return Some(msg);
+        }
+    }
+    return None;
+}
+
+/// f without its leading zero coefficients
+fn trim_poly(f: &[Integer]) -> &[Integer] {
+    let len = f.iter().rposition(|coef| *coef != 0).map_or(1, |i| i + 1);
+    return &f[0..len];
+}
+
+/// Recovers (m1, m2) from c1 = m1^e, c2 = m2^e mod N when m1 = f(m2) for a known polynomial f
+/// (Franklin-Reiter)
+/// m2 is a root of both f(x)^e - c1 and x^e - c2, so their gcd is usually x - m2
+/// Everything is reduced mod (f(x)^e - c1, N), so the work grows with e deg f and not with the
+/// size of the coefficients, and the gcd uses the half-GCD
+pub fn franklin_reiter(
+    n: &Integer,
+    e: &Integer,
+    c1: &Integer,
+    c2: &Integer,
+    relation: &[Integer],
+) -> Result<(Integer, Integer), FranklinReiterError> {
+    let relation: Vec<Integer> = relation
+        .iter()
+        .map(|coef| Integer::from(coef % n))
+        .collect();
+    let relation = trim_poly(&relation).to_vec();
+    assert!(relation.len() > 1, "the relation has to depend on m2");
+    // f(x)^e - c1 needs a unit leading coefficient for the reductions below
+    if let Err(factor) = cryptlib::unit_inverse(&relation[relation.len() - 1], n) {
+        return franklin_reiter_factored(n, e, c2, &relation, &factor);
+    }
+    let mut g1 = cryptlib::exp_poly_zn(&relation, e, n);
+    g1[0] -= c1;
+    g1[0] %= n;
+    if g1[0] < 0 {
+        g1[0] += n;
+    }
+    // x^e - c2 mod g1, which has the same gcd with g1 and a degree below it
+    let x = vec![Integer::from(0), Integer::from(1)];
+    let mut g2 = cryptlib::powmod(&x, e, &g1, n);
+    g2[0] -= c2;
+
+    let r = match cryptlib::poly_gcd_zn(&g1, &g2, n) {
+        Ok(r) => r,
+        Err(factor) => return franklin_reiter_factored(n, e, c2, &relation, &factor),
+    };
+    if r.len() != 2 {
+        return Err(FranklinReiterError::NonLinearGcd(r.len() - 1));
+    }
+    // r is monic, x + r0
+    let m2 = (n - Integer::from(&r[0] % n)) % n;
+    let m1 = cryptlib::eval_poly(&m2, &relation, n);
+    return Ok((m1, m2));
+}
+
+/// Decrypts c2 directly once the gcd has revealed a factor of N
+fn franklin_reiter_factored(
+    n: &Integer,
+    e: &Integer,
+    c2: &Integer,
+    relation: &Vec<Integer>,
+    factor: &Integer,
+) -> Result<(Integer, Integer), FranklinReiterError> {
+    let phi = Integer::from(factor - 1) * (Integer::from(n / factor) - 1);
+    let d =
+        cryptlib::unit_inverse(e, &phi).map_err(|_| FranklinReiterError::Factor(factor.clone()))?;
+    let m2 = cryptlib::fast_power(c2, &d, n);
+    let m1 = cryptlib::eval_poly(&m2, relation, n);
+    return Ok((m1, m2));
+}
+
+/// Whether random pads of pad_bits bits are within the N^(1/e^2) bound of the short pad attack
+pub fn short_pad_in_bound(n: &Integer, e: &Integer, pad_bits: u32) -> bool {
+    let e_squared = Integer::from(e.square_ref());
+    return Integer::from(pad_bits) * e_squared < n.significant_bits() - 1;
+}
+
+/// Recovers M from encryptions of m1 = 2^pad_bits M + r1 and m2 = 2^pad_bits M + r2 with
+/// r1, r2 < 2^pad_bits (Coppersmith's short pad attack)
+/// Δ = m2 - m1 is a root of the resultant in x of x^e - c1 and (x + y)^e - c2, which has degree
+/// e^2 and is solved with Coppersmith's method; Franklin-Reiter with m1 = m2 - Δ then gives m2
+pub fn short_pad_attack(
+    n: &Integer,
+    e: &Integer,
+    c1: &Integer,
+    c2: &Integer,
+    pad_bits: u32,
+) -> Option<Integer> {
+    let e_usize = e.to_usize()?;
+
+    // g1[i][j] is the coefficient of x^i y^j
+    let mut g1 = vec![vec![Integer::from(0); e_usize + 1]; e_usize + 1];
+    g1[0][0] -= c1;
+    g1[e_usize][0] += 1;
+    let mut g2 = vec![vec![Integer::from(0); e_usize + 1]; e_usize + 1];
+    g2[0][0] -= c2;
+    for i in 0..=e_usize {
+        g2[i][e_usize - i] += Integer::from(Integer::binomial_u(e_usize as u32, i as u32));
+    }
+
+    let mut resultant = match cryptlib::resultant_interpolate(&g1, &g2, n) {
+        Ok(resultant) => trim_poly(&resultant).to_vec(),
+        Err(_) => return None,
+    };
+    let inverse = cryptlib::unit_inverse(&resultant[resultant.len() - 1], n).ok()?;
+    for coef in resultant.iter_mut() {
+        *coef = Integer::from(&*coef * &inverse) % n;
+    }
+
+    let (m, epsilon_denom) = coppersmith_parameters(n, (resultant.len() - 1) as u32, pad_bits)?;
+    let delta = cryptlib::coppersmith(&resultant, n, m, epsilon_denom)?;
+    let (_m1, m2) = franklin_reiter(n, e, c1, c2, &[-delta, Integer::from(1)]).ok()?;
+    return Some(m2 >> pad_bits);
+}
+
+/// Coppersmith parameters (m, epsilon_denom) for a degree d polynomial with roots below
+/// 2^root_bits: the smallest 1/ε giving X = N^(1/d - 1/ε) > 2^root_bits, then the smallest m up
+/// to 4 for which det(L)^(1/w) 2^(w/4) < N^m / sqrt(w) holds for the w = d(m + 1) dimensional
+/// lattice
+fn coppersmith_parameters(n: &Integer, d: u32, root_bits: u32) -> Option<(u32, u32)> {
+    let log2_n = Float::with_val(64, n).log2().to_f64();
+    let slack = 1.0 / d as f64 - root_bits as f64 / log2_n;
+    if slack <= 0.0 {
+        return None;
+    }
+    let mut epsilon_denom = (1.0 / slack).ceil() as u32;
+    if log2_n * (1.0 / d as f64 - 1.0 / epsilon_denom as f64) <= root_bits as f64 {
+        epsilon_denom += 1;
+    }
+    let log2_x = log2_n * (epsilon_denom - d) as f64 / (d * epsilon_denom) as f64;
+
+    for m in 1..=4 {
+        let w = (d * (m + 1)) as f64;
+        // the rows x^u f^v N^(m - v) have diagonal N^(m - v) X^(u + vd)
+        let mut log2_det = 0.0;
+        for v in 0..=m {
+            for u in 0..d {
+                log2_det += (m - v) as f64 * log2_n + (u + v * d) as f64 * log2_x;
+            }
+        }
+        if log2_det / w + w / 4.0 < m as f64 * log2_n - 0.5 * w.log2() {
+            return Some((m, epsilon_denom));
+        }
+    }
+    return None;
+}
+
+/// Recovers the bytes of m from c = m^e mod N when all of m is known but one part of it
+/// (stereotyped message)
+/// With m = K + 2^(8 offset) x for the known bytes K, the unknown x is a small root of
+/// (x + K 2^(-8 offset))^e - c 2^(-8 e offset) mod N, which Coppersmith's method finds when
+/// x < N^(1/e)
+pub fn stereotyped_message_attack(
+    n: &Integer,
+    e: &Integer,
+    c: &Integer,
+    template: &[TemplatePart],
+) -> Result<Vec<u8>, StereotypedMessageError> {
+    // only one unknown keeps the polynomial univariate
+    let unknown_parts = template
+        .iter()
+        .filter(|part| matches!(part, TemplatePart::Unknown(_)))
+        .count();
+    if unknown_parts != 1 {
+        return Err(StereotypedMessageError::UnknownParts(unknown_parts));
+    }
+
+    // offsets are in bytes from the end of the message, its least significant byte
+    let mut known = Integer::from(0);
+    let mut unknown = (0, 0);
+    let mut length = 0;
+    for part in template.iter().rev() {
+        match part {
+            TemplatePart::Known(bytes) => {
+                known += Integer::from_digits(bytes, Order::Msf) << (8 * length);
+                length += bytes.len() as u32;
+            }
+            TemplatePart::Unknown(bits) => {
+                unknown = (length, *bits);
+                length += bits.div_ceil(8);
+            }
+        }
+    }
+    let (offset, bits) = unknown;
+
+    let shift_inverse = cryptlib::find_inverse(&(Integer::from(1) << (8 * offset)), n);
+    let shifted_known = Integer::from(&known * &shift_inverse) % n;
+    let mut f = cryptlib::exp_poly(&vec![shifted_known, Integer::from(1)], e);
+    f[0] -= c * cryptlib::fast_power(&shift_inverse, e, n);
+    for coef in f.iter_mut() {
+        *coef %= n;
+        if *coef < 0 {
+            *coef += n;
+        }
+    }
+
+    let (m, epsilon_denom) = e
+        .to_u32()
+        .and_then(|e| coppersmith_parameters(n, e, bits))
+        .ok_or(StereotypedMessageError::OutOfBound)?;
+    let x = cryptlib::coppersmith(&f, n, m, epsilon_denom)
+        .filter(|x| *x >= 0 && x.significant_bits() <= bits)
+        .ok_or(StereotypedMessageError::NoRoot)?;
+    let msg = known + (x << (8 * offset));
+    let digits = msg.to_digits::<u8>(Order::Msf);
+    let mut bytes = vec![0; length as usize - digits.len()];
+    bytes.extend(digits);
+    return Ok(bytes);
+}
+
+/// Factors N from a private key pair (e, d), for any d with ed = 1 mod λ(N)
+/// ed - 1 = 2^s r is a multiple of λ(N), so for random g the sequence g^r, g^2r, ..., g^(ed - 1) = 1
+/// reaches 1, and half the time the last value before it is a square root of 1 other than ±1,
+/// sharing a factor with N
+pub fn factor_from_private_key(n: &Integer, e: &Integer, d: &Integer) -> Option<RsaPrivateKey> {
+    let ed_minus_one: Integer = Integer::from(e * d) - 1;
+    if ed_minus_one <= 0 {
+        return None;
+    }
+    let s = ed_minus_one.find_one(0).unwrap();
+    let r = Integer::from(&ed_minus_one >> s);
+
+    let mut rand = RandState::new();
+    // each try fails with probability at most 1/2
+    for _ in 0..100 {
+        let g = Integer::from(Integer::from(n - 3).random_below_ref(&mut rand)) + 2;
+        let common = cryptlib::gcd(&g, n);
+        if common != 1 {
+            return Some(RsaPrivateKey::from_factors(
+                n,
+                e,
+                d,
+                &common,
+                &Integer::from(n / &common),
+            ));
+        }
+        let mut x = cryptlib::fast_power(&g, &r, n);
+        for _ in 0..s {
+            let x_squared = Integer::from(x.square_ref()) % n;
+            if x_squared == 1 {
+                if x != 1 && x != Integer::from(n - 1) {
+                    let p = cryptlib::gcd(&Integer::from(&x - 1), n);
+                    return Some(RsaPrivateKey::from_factors(
+                        n,
+                        e,
+                        d,
+                        &p,
+                        &Integer::from(n / &p),
+                    ));
+                }
+                break;
+            }
+            x = x_squared;
+        }
+    }
+    return None;
+}
+
+/// Factors N from (e, d) without randomness when d = e^-1 mod φ(N) and e < N^(1/2) / 3
+/// ed - 1 = kφ(N) with N - φ(N) < 3 N^(1/2) and k < e, so k = ceil((ed - 1) / N) exactly,
+/// which gives φ(N) and then p + q = N + 1 - φ(N)
+pub fn factor_from_private_key_small_e(
+    n: &Integer,
+    e: &Integer,
+    d: &Integer,
+) -> Option<RsaPrivateKey> {
+    let ed_minus_one: Integer = Integer::from(e * d) - 1;
+    if ed_minus_one <= 0 {
+        return None;
+    }
+    let k = Integer::from(&ed_minus_one - 1) / n + 1;
+    let (phi, rem) = ed_minus_one.div_rem(k);
+    if rem != 0 {
+        return None;
+    }
+    let (p, q) = factor_from_sum(n, &(Integer::from(n + 1) - phi))?;
+    return Some(RsaPrivateKey::from_factors(n, e, d, &p, &q));
+}
+
+/// Factors N from a faulty RSA-CRT signature s of m (Lenstra)
+/// A fault in s_p leaves s^e = m mod q but not mod p, so gcd(s^e - m, N) = q
+pub fn lenstra_fault_attack(
+    n: &Integer,
+    e: &Integer,
+    m: &Integer,
+    faulty_s: &Integer,
+) -> Option<(Integer, Integer)> {
+    let diff = cryptlib::fast_power(faulty_s, e, n) - Integer::from(m % n);
+    return factors_from_gcd(n, &diff);
+}
+
+/// Factors N from a correct and a faulty RSA-CRT signature of the same message (Boneh, DeMillo
+/// and Lipton)
+/// The two agree mod q but not mod p when the fault hit s_p, so gcd(s - s', N) = q
+pub fn bellcore_fault_attack(
+    n: &Integer,
+    s: &Integer,
+    faulty_s: &Integer,
+) -> Option<(Integer, Integer)> {
+    return factors_from_gcd(n, &Integer::from(s - faulty_s));
+}
+
+/// (p, q) with p >= q from a = 0 mod one factor of N only
+fn factors_from_gcd(n: &Integer, a: &Integer) -> Option<(Integer, Integer)> {
+    let g = cryptlib::gcd(&Integer::from(a.abs_ref()), n);
+    if g == 1 || g == *n {
+        return None;
+    }
+    let other = Integer::from(n / &g);
+    return Some(if g > other { (g, other) } else { (other, g) });
+}
+
+/// Recovers m from c1 = m^e1, c2 = m^e2 mod N (see common_modulus_attack_multi)
+pub fn common_modulus_attack(
+    n: &Integer,
+    e1: &Integer,
+    c1: &Integer,
+    e2: &Integer,
+    c2: &Integer,
+) -> Option<Integer> {
+    return common_modulus_attack_multi(n, &[(e1.clone(), c1.clone()), (e2.clone(), c2.clone())]);
+}
+
+/// Recovers m from ciphertexts (e_i, m^e_i mod N) under one modulus
+/// With sum a_i e_i = g = gcd(e_1, ..., e_k), prod c_i^a_i = m^g mod N, which is m when g = 1;
+/// otherwise m is its integer g-th root, found only when m^g < N
+pub fn common_modulus_attack_multi(
+    n: &Integer,
+    ciphertexts: &[(Integer, Integer)],
+) -> Option<Integer> {
+    let mut g = Integer::from(0);
+    let mut coefficients: Vec<Integer> = Vec::new();
+    for (e, _c) in ciphertexts {
+        if coefficients.is_empty() {
+            g = e.clone();
+            coefficients.push(Integer::from(1));
+            continue;
+        }
+        // s g + t e = gcd(g, e), so every earlier coefficient scales by s
+        let (s, t) = cryptlib::bezout(&g, e);
+        for coefficient in coefficients.iter_mut() {
+            *coefficient *= &s;
+        }
+        coefficients.push(t);
+        g = cryptlib::gcd(&g, e);
+    }
+
+    let mut m_g = Integer::from(1);
+    for ((_e, c), a) in ciphertexts.iter().zip(&coefficients) {
+        // a negative exponent is a positive one on the inverse
+        let term = if *a < 0 {
+            cryptlib::fast_power(&cryptlib::find_inverse(c, n), &Integer::from(-a), n)
+        } else {
+            cryptlib::fast_power(c, a, n)
+        };
+        m_g = m_g * term % n;
+    }
+    if g == 1 {
+        return Some(m_g);
+    }
+    let (m, rem) = m_g.root_rem(Integer::new(), g.to_u32()?);
+    if rem != 0 {
+        return None;
+    }
+    return Some(m);
+}
+
+/// Returns (p, q) with p >= q, p q = N and p + q = s, if there are any
+pub fn factor_from_sum(n: &Integer, s: &Integer) -> Option<(Integer, Integer)> {
+    // p, q are the roots of z^2 - s z + N
+    let disc = Integer::from(s.square_ref()) - Integer::from(n * 4);
+    if *s <= 0 || disc < 0 || !disc.is_perfect_square() {
+        return None;
+    }
+    let root = disc.sqrt();
+    let p = Integer::from(s + &root) / 2;
+    let q = Integer::from(s - &root) / 2;
+    if Integer::from(&p * &q) != *n {
+        return None;
+    }
+    return Some((p, q));
+}
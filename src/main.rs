@@ -13,6 +13,11 @@ use std::{io, str, vec};
 
 mod cryptlib;
 mod cryptlib_bv;
+mod cryptlib_factor;
+mod cryptlib_mv;
+mod cryptlib_padding;
+mod cryptlib_partial_key;
+mod cryptlib_rsa;
 
 fn main() {
     // component tests
@@ -27,7 +32,27 @@ fn main() {
     // test_determinant();
     // test_inv_quad();
     // test_coppersmith_bv();
+    // test_coppersmith_bv_auto();
+    // test_coppersmith_bv_degrees();
+    // test_boneh_durfee();
+    // test_wiener();
+    // test_common_modulus();
+    // test_factor_from_private_key();
+    // test_stereotyped_message();
+    // test_factor_with_known_bits();
+    // test_crt_fault();
+    // test_bleichenbacher();
+    // test_manger();
     // test_real_bv_polys();
+    // test_factor_poly_z();
+    // test_fast_multiply();
+    // test_poly_powmod();
+    // test_poly_half_gcd();
+    // test_poly_gcd_factor();
+    // test_multipoint();
+    // test_multivariate();
+    // test_groebner();
+    // test_resultant_mv();
 
     // attacks
     // test_hastad_broadcast();
@@ -71,6 +96,1281 @@ fn test_real_bv_polys() {
     }
 }
 
+fn test_factor_poly_z() {
+    let to_poly =
+        |coeffs: &[i64]| -> Vec<Integer> { coeffs.iter().map(|x| Integer::from(*x)).collect() };
+
+    // 6 (x - 3)^2 (x^2 + 1) (2x^3 + x - 5) x
+    let mut f = to_poly(&[6]);
+    f = cryptlib::multiply_poly(&f, &to_poly(&[-3, 1]));
+    f = cryptlib::multiply_poly(&f, &to_poly(&[-3, 1]));
+    f = cryptlib::multiply_poly(&f, &to_poly(&[1, 0, 1]));
+    f = cryptlib::multiply_poly(&f, &to_poly(&[-5, 1, 0, 2]));
+    f = cryptlib::multiply_poly(&f, &to_poly(&[0, 1]));
+    println!("f = {:?}", f);
+
+    let (content, factors) = cryptlib_factor::factor_poly_z(&f);
+    println!("content {}", content);
+    let mut product = vec![content];
+    for (factor, multiplicity) in &factors {
+        println!(" {:?} ^{}", factor, multiplicity);
+        for _ in 0..*multiplicity {
+            product = cryptlib::multiply_poly(&product, factor);
+        }
+    }
+    assert!(product == f, "factors do not multiply back to f");
+    assert!(factors.len() == 4);
+    assert!(cryptlib_factor::integer_roots(&f) == vec![Integer::from(0), Integer::from(3)]);
+
+    // Swinnerton-Dyer polynomial for 2, 3, 5, 7: irreducible but splits into >= 8 factors mod every p
+    let sd = to_poly(&[
+        46225, 0, -5596840, 0, 13950764, 0, -7453176, 0, 1513334, 0, -141912, 0, 6476, 0, -136, 0,
+        1,
+    ]);
+    let now = Instant::now();
+    let (_content, factors) = cryptlib_factor::factor_poly_z(&sd);
+    println!(
+        "swinnerton-dyer: {} factor(s) in {:?}",
+        factors.len(),
+        now.elapsed()
+    );
+    assert!(factors.len() == 1);
+
+    // (x^8 - 1)(x^3 + 3x + 7) has five irreducible factors, and x^4 + 1 among them splits mod
+    // every prime, so recombination has to put its modular factors back together
+    let g = cryptlib::multiply_poly(
+        &to_poly(&[-1, 0, 0, 0, 0, 0, 0, 0, 1]),
+        &to_poly(&[7, 3, 0, 1]),
+    );
+    let (_content, factors) = cryptlib_factor::factor_poly_z(&g);
+    for (factor, multiplicity) in &factors {
+        println!(" {:?} ^{}", factor, multiplicity);
+    }
+    assert!(factors.len() == 5);
+}
+
+fn test_fast_multiply() {
+    let mut rand = RandState::new();
+    let mut random_poly = |len: usize, bits: u32| -> Vec<Integer> {
+        (0..len)
+            .map(|_| {
+                Integer::from(Integer::random_bits(bits, &mut rand))
+                    - (Integer::from(1) << (bits - 1))
+            })
+            .collect()
+    };
+
+    for (len, bits) in [
+        (1, 8),
+        (5, 8),
+        (30, 8),
+        (100, 1),
+        (100, 3),
+        (100, 512),
+        (333, 64),
+        (3000, 20),
+    ] {
+        let f = random_poly(len, bits);
+        let g = random_poly(len / 2 + 1, bits);
+        // multiply_poly drops zero leading terms, the fast versions keep the full length
+        let expected = cryptlib::multiply_poly(&f, &g);
+        let same = |prod: Vec<Integer>| {
+            prod[..expected.len()] == expected[..] && prod[expected.len()..].iter().all(|x| *x == 0)
+        };
+        assert!(
+            same(cryptlib::multiply_poly_karatsuba(&f, &g)),
+            "karatsuba {}",
+            len
+        );
+        assert!(
+            same(cryptlib::multiply_poly_kronecker(&f, &g)),
+            "kronecker {}",
+            len
+        );
+        assert!(same(cryptlib::multiply_poly_ntt(&f, &g)), "ntt {}", len);
+        assert!(same(cryptlib::multiply_poly_fast(&f, &g)), "fast {}", len);
+    }
+
+    let n = Integer::from(1000003);
+    let f: Vec<Vec<Integer>> = (0..4).map(|_| random_poly(3, 30)).collect();
+    let g: Vec<Vec<Integer>> = (0..2).map(|_| random_poly(5, 30)).collect();
+    let prod = cryptlib_bv::multiply_poly_bv(&f, &g, &n);
+    let x = Integer::from(12345);
+    let y = Integer::from(-678);
+    let lhs = cryptlib_bv::eval_poly_bv(&prod, &x, &y, &n);
+    let rhs = cryptlib_bv::eval_poly_bv(&f, &x, &y, &n) * cryptlib_bv::eval_poly_bv(&g, &x, &y, &n);
+    assert!((lhs - rhs) % &n == 0, "bivariate product");
+
+    type PolyMultiply = fn(&[Integer], &[Integer]) -> Vec<Integer>;
+    println!(
+        "{:>6} {:>6} {:>12} {:>12} {:>12} {:>12}",
+        "len", "bits", "schoolbook", "karatsuba", "kronecker", "ntt"
+    );
+    for (len, bits) in [
+        (16, 64),
+        (32, 64),
+        (64, 64),
+        (128, 64),
+        (256, 2048),
+        (1024, 64),
+        (4096, 32),
+        (16384, 32),
+    ] {
+        let f = random_poly(len, bits);
+        let g = random_poly(len, bits);
+        let mut times = Vec::new();
+        let algorithms: [PolyMultiply; 4] = [
+            |f, g| cryptlib::multiply_poly(f, g),
+            cryptlib::multiply_poly_karatsuba,
+            cryptlib::multiply_poly_kronecker,
+            cryptlib::multiply_poly_ntt,
+        ];
+        for (i, algorithm) in algorithms.iter().enumerate() {
+            let now = Instant::now();
+            // schoolbook is too slow to bother with at the largest size
+            if i > 0 || len <= 4096 {
+                algorithm(&f, &g);
+            }
+            times.push(now.elapsed());
+        }
+        println!(
+            "{:>6} {:>6} {:>12?} {:>12?} {:>12?} {:>12?}",
+            len, bits, times[0], times[1], times[2], times[3]
+        );
+    }
+}
+
+fn test_poly_powmod() {
+    let to_poly =
+        |coeffs: &[i64]| -> Vec<Integer> { coeffs.iter().map(|x| Integer::from(*x)).collect() };
+
+    // exponentiation by squaring agrees with repeated multiplication
+    let f = to_poly(&[20, 3]);
+    let mut expected = to_poly(&[1]);
+    for e in 0..12 {
+        if e > 0 {
+            expected = cryptlib::multiply_poly(&expected, &f);
+        }
+        let result = cryptlib::exp_poly(&f, &Integer::from(e));
+        assert!(result[..expected.len()] == expected[..], "exp_poly e={}", e);
+    }
+    let f_bv = vec![to_poly(&[1, 2]), to_poly(&[3, 0])];
+    let result = cryptlib_bv::exp_poly_bv(&f_bv, &Integer::from(7), &Integer::from(-1));
+    let x = Integer::from(5);
+    let y = Integer::from(-2);
+    let expected = cryptlib_bv::eval_poly_bv(&f_bv, &x, &y, &Integer::from(-1)).pow(7);
+    assert!(cryptlib_bv::eval_poly_bv(&result, &x, &y, &Integer::from(-1)) == expected);
+
+    // (3x + 20)^65537 mod n, which was infeasible by repeated multiplication
+    let mut rand = RandState::new();
+    let n = Integer::from(Integer::random_bits(512, &mut rand)) | 1;
+    let e = Integer::from(65537);
+    let now = Instant::now();
+    let g = cryptlib::exp_poly_zn(&f, &e, &n);
+    println!("(3x + 20)^{} mod n in {:?}", e, now.elapsed());
+    assert!(g.len() == 65538);
+    let point = Integer::from(123456789);
+    let lhs = cryptlib::eval_poly(&point, &g, &n);
+    let rhs = cryptlib::fast_power(&cryptlib::eval_poly(&point, &f, &n), &e, &n);
+    assert!(lhs == rhs, "exp_poly_zn");
+
+    // powmod against long division, including an exponent beyond i32
+    let modulus = to_poly(&[7, 0, 5, 1, 0, 0, 1]);
+    let x = to_poly(&[0, 1]);
+    let mut expected = to_poly(&[1]);
+    for _ in 0..40 {
+        expected =
+            cryptlib::divide_poly_zn(&cryptlib::multiply_poly_zn(&expected, &x, &n), &modulus, &n)
+                .unwrap()
+                .1;
+    }
+    let expected = cryptlib::rem_poly_zn(&expected, &modulus, &n);
+    assert!(
+        cryptlib::powmod(&x, &Integer::from(40), &modulus, &n) == expected,
+        "powmod"
+    );
+    let big_e = Integer::from(1) << 100;
+    let a = cryptlib::powmod(&f, &big_e, &modulus, &n);
+    let b = cryptlib::powmod(&f, &(big_e.clone() + 3), &modulus, &n);
+    let f_cubed = cryptlib::powmod(&f, &Integer::from(3), &modulus, &n);
+    let ab = cryptlib::rem_poly_zn(&cryptlib::multiply_poly_zn(&a, &f_cubed, &n), &modulus, &n);
+    assert!(b == ab, "powmod big exponent");
+
+    // modular composition against Horner evaluation at h
+    let poly = to_poly(&[5, -1, 0, 4, 9, 2, 0, 0, 1, 3, 7]);
+    let h = to_poly(&[2, 1, 0, 3, 0, 0, 0, 0, 11]);
+    let mut expected = to_poly(&[0]);
+    for coef in poly.iter().rev() {
+        expected = cryptlib::multiply_poly_zn(&expected, &h, &n);
+        expected[0] += coef;
+        expected = cryptlib::rem_poly_zn(&expected, &modulus, &n);
+    }
+    assert!(
+        cryptlib::compose_mod(&poly, &h, &modulus, &n) == expected,
+        "compose_mod"
+    );
+
+    // inputs of at least twice the degree of g are reduced before the shared inverse applies
+    let f_long = to_poly(&[3, 0, 0, 2, 1]);
+    let modulus = to_poly(&[1, 0, 1]);
+    let f_long_rem = cryptlib::rem_poly_zn(&f_long, &modulus, &n);
+    assert!(f_long_rem == cryptlib::rem_poly_zn(&to_poly(&[4, -2]), &modulus, &n));
+    assert!(
+        cryptlib::powmod(&f_long, &Integer::from(1), &modulus, &n) == f_long_rem,
+        "powmod deg f >= 2 deg g"
+    );
+    let f_long_5 = cryptlib::exp_poly_zn(&f_long, &Integer::from(5), &n);
+    assert!(
+        cryptlib::powmod(&f_long, &Integer::from(5), &modulus, &n)
+            == cryptlib::rem_poly_zn(&f_long_5, &modulus, &n),
+        "powmod deg f >= 2 deg g"
+    );
+    assert!(
+        cryptlib::compose_mod(&x, &f_long, &modulus, &n) == f_long_rem,
+        "compose_mod deg h >= 2 deg g"
+    );
+    assert!(
+        cryptlib::compose_mod(&f_long, &x, &modulus, &n) == f_long_rem,
+        "compose_mod deg f >= 2 deg g"
+    );
+
+    // an empty f is the zero polynomial
+    let zero = vec![Integer::from(0)];
+    assert!(
+        cryptlib::rem_poly_zn(&[], &modulus, &n) == zero,
+        "rem_poly_zn of []"
+    );
+    assert!(
+        cryptlib::powmod(&[], &Integer::from(5), &modulus, &n) == zero,
+        "powmod of []"
+    );
+    assert!(
+        cryptlib::compose_mod(&[], &x, &modulus, &n) == zero,
+        "compose_mod of []"
+    );
+}
+
+fn test_poly_half_gcd() {
+    let mut rand = RandState::new();
+    let p = Integer::from(Integer::random_bits(127, &mut rand)).next_prime();
+    let mut random_poly = |len: usize| -> Vec<Integer> {
+        let mut f: Vec<Integer> = (0..len)
+            .map(|_| Integer::from(p.random_below_ref(&mut rand)))
+            .collect();
+        f[len - 1] = Integer::from(1);
+        f
+    };
+
+    // gcd of a = g u and b = g v against the classical Euclidean algorithm, which needs deg g > 0
+    for (g_len, u_len, v_len) in [
+        (2, 5, 3),
+        (4, 40, 40),
+        (10, 200, 150),
+        (50, 700, 300),
+        (3, 2000, 1999),
+    ] {
+        let g = random_poly(g_len);
+        let a = cryptlib::multiply_poly_zn(&g, &random_poly(u_len), &p);
+        let b = cryptlib::multiply_poly_zn(&g, &random_poly(v_len), &p);
+
+        let now = Instant::now();
+        let (r, _s, _t) = cryptlib::poly_extended_euclidean_zn(&a, &b, &p).unwrap();
+        let classical_time = now.elapsed();
+        let r: Vec<Integer> = r.into_iter().map(|x| ((x % &p) + &p) % &p).collect();
+        let r_len = r.iter().rposition(|x| *x != 0).unwrap() + 1;
+        let lead_inv = cryptlib::find_inverse(&r[r_len - 1], &p);
+        let expected: Vec<Integer> = r[..r_len]
+            .iter()
+            .map(|x| Integer::from(x * &lead_inv) % &p)
+            .collect();
+
+        let now = Instant::now();
+        let gcd = cryptlib::poly_gcd_zn(&a, &b, &p).unwrap();
+        let gcd_time = now.elapsed();
+        let (gcd_ext, s, t) = cryptlib::poly_extended_gcd_zn(&a, &b, &p).unwrap();
+        let extended_time = now.elapsed() - gcd_time;
+        println!(
+            "deg {:>5} / {:>5}: classical {:>12?} gcd only {:>12?} extended {:>12?}",
+            a.len() - 1,
+            b.len() - 1,
+            classical_time,
+            gcd_time,
+            extended_time
+        );
+        assert!(
+            gcd == expected && gcd_ext == expected,
+            "gcd of degree {}",
+            g_len - 1
+        );
+        let sa = cryptlib::multiply_poly_zn(&s, &a, &p);
+        let tb = cryptlib::multiply_poly_zn(&t, &b, &p);
+        let mut combination = vec![Integer::from(0); std::cmp::max(sa.len(), tb.len())];
+        for (i, val) in sa.into_iter().enumerate() {
+            combination[i] += val;
+        }
+        for (i, val) in tb.into_iter().enumerate() {
+            combination[i] += val;
+        }
+        let combination: Vec<Integer> = combination.into_iter().map(|x| x % &p).collect();
+        assert!(combination[..gcd.len()] == gcd[..], "bezout identity");
+        assert!(
+            combination[gcd.len()..].iter().all(|x| *x == 0),
+            "bezout identity"
+        );
+    }
+
+    // Franklin-Reiter with e = 65537: gcd((3x + 20)^e - c1, x^e - c2) = x - m2
+    let n_bits = 1024;
+    let e = Integer::from(65537);
+    let mut primes = Vec::new();
+    while primes.len() < 2 {
+        let prime = Integer::from(Integer::random_bits(n_bits / 2, &mut rand)).next_prime();
+        if Integer::from(&prime % &e) != 1 {
+            primes.push(prime);
+        }
+    }
+    let n = Integer::from(&primes[0] * &primes[1]);
+    let f = vec![Integer::from(20), Integer::from(3)];
+    let msg2 = Integer::from_digits(
+        "a related message padded with a known affine relation".as_bytes(),
+        Order::Lsf,
+    );
+    let msg1 = cryptlib::eval_poly(&msg2, &f, &n);
+    let c1 = cryptlib::fast_power(&msg1, &e, &n);
+    let c2 = cryptlib::fast_power(&msg2, &e, &n);
+
+    let now = Instant::now();
+    let mut g1 = cryptlib::exp_poly_zn(&f, &e, &n);
+    g1[0] -= &c1;
+    let mut g2 = cryptlib::exp_poly_zn(&[Integer::from(0), Integer::from(1)], &e, &n);
+    g2[0] -= &c2;
+    println!("built degree {} polynomials in {:?}", e, now.elapsed());
+
+    let now = Instant::now();
+    let r = cryptlib::poly_gcd_zn(&g1, &g2, &n).unwrap();
+    println!("half-gcd in {:?}", now.elapsed());
+    assert!(r.len() == 2, "gcd is not linear");
+    let recovered_m2 = Integer::from(&n - &r[0]) % &n;
+    assert!(recovered_m2 == msg2);
+    let msg_bytes = recovered_m2.to_digits::<u8>(Order::Lsf);
+    println!("recovered m2: {}", String::from_utf8(msg_bytes).unwrap());
+}
+
+fn test_poly_gcd_factor() {
+    let mut rand = RandState::new();
+    let p = Integer::from(Integer::random_bits(256, &mut rand)).next_prime();
+    let q = Integer::from(Integer::random_bits(256, &mut rand)).next_prime();
+    let n = Integer::from(&p * &q);
+    let mut random_poly = |len: usize| -> Vec<Integer> {
+        let mut f: Vec<Integer> = (0..len)
+            .map(|_| Integer::from(n.random_below_ref(&mut rand)))
+            .collect();
+        f[len - 1] = Integer::from(1);
+        f
+    };
+
+    // a = quotient b + r where the leading coefficient of r is a multiple of p
+    for (quotient_len, b_len, r_len) in [(2, 3, 2), (5, 20, 12), (100, 300, 200)] {
+        let b = random_poly(b_len);
+        let mut r = random_poly(r_len);
+        r[r_len - 1] = Integer::from(&p * 12345);
+        let mut a = cryptlib::multiply_poly_zn(&random_poly(quotient_len), &b, &n);
+        for (val, r_val) in a.iter_mut().zip(&r) {
+            *val += r_val;
+            *val %= &n;
+        }
+
+        assert!(
+            cryptlib::divide_poly_zn(&b, &r, &n) == Err(p.clone()),
+            "divide_poly_zn"
+        );
+        assert!(
+            cryptlib::poly_extended_euclidean_zn(&a, &b, &n) == Err(p.clone()),
+            "poly_extended_euclidean_zn"
+        );
+        assert!(
+            cryptlib::poly_gcd_zn(&a, &b, &n) == Err(p.clone()),
+            "poly_gcd_zn"
+        );
+        assert!(
+            cryptlib::poly_extended_gcd_zn(&a, &b, &n) == Err(p.clone()),
+            "poly_extended_gcd_zn"
+        );
+        println!("degree {} / {}: found factor {}", a.len() - 1, b_len - 1, p);
+    }
+
+    // a leading coefficient that is a multiple of n is a zero coefficient, not the factor n
+    let a = random_poly(30);
+    let b = random_poly(10);
+    let mut b_padded = b.clone();
+    b_padded.push(n.clone());
+    assert!(
+        cryptlib::divide_poly_zn(&a, &b_padded, &n) == cryptlib::divide_poly_zn(&a, &b, &n),
+        "divide_poly_zn with a zero leading coefficient"
+    );
+    assert!(
+        cryptlib::poly_gcd_zn(&a, &b_padded, &n) == cryptlib::poly_gcd_zn(&a, &b, &n),
+        "poly_gcd_zn with a zero leading coefficient"
+    );
+    assert!(cryptlib::divide_poly_zn(&a, &b_padded, &n).is_ok());
+}
+
+fn test_multipoint() {
+    let mut rand = RandState::new();
+    let n = Integer::from(Integer::random_bits(512, &mut rand)) | 1;
+    let mut random_poly = |len: usize, bound: &Integer| -> Vec<Integer> {
+        (0..len)
+            .map(|_| Integer::from(bound.random_below_ref(&mut rand)))
+            .collect()
+    };
+
+    // multipoint evaluation against one point at a time, mod n and over the integers
+    let f = random_poly(300, &n);
+    let points = random_poly(200, &n);
+    let values = cryptlib::eval_poly_multi(&f, &points, &n);
+    for (point, value) in points.iter().zip(&values) {
+        let expected = (cryptlib::eval_poly(point, &f, &n) + &n) % &n;
+        assert!(*value == expected, "eval_poly_multi mod n");
+    }
+    let small = Integer::from(1000);
+    let f_z: Vec<Integer> = random_poly(20, &small)
+        .into_iter()
+        .map(|x| x - 500)
+        .collect();
+    let points_z: Vec<Integer> = (-15..15).map(Integer::from).collect();
+    let values_z = cryptlib::eval_poly_multi(&f_z, &points_z, &Integer::from(-1));
+    for (point, value) in points_z.iter().zip(&values_z) {
+        assert!(
+            *value == cryptlib::eval_poly(point, &f_z, &Integer::from(-1)),
+            "eval_poly_multi over Z"
+        );
+    }
+
+    // interpolation through the values recovers the polynomial
+    let g = random_poly(200, &n);
+    let g_values = cryptlib::eval_poly_multi(&g, &points, &n);
+    assert!(
+        cryptlib::interpolate_zn(&points, &g_values, &n).unwrap() == g,
+        "interpolate_zn"
+    );
+    let points_q: Vec<Rational> = (0..20).map(|i| Rational::from((2 * i - 7, 3))).collect();
+    let f_q: Vec<Rational> = f_z.iter().map(|x| Rational::from((x, 7))).collect();
+    let values_q: Vec<Rational> = points_q
+        .iter()
+        .map(|x| {
+            f_q.iter()
+                .rev()
+                .fold(Rational::from(0), |acc, coef| acc * x + coef)
+        })
+        .collect();
+    assert!(
+        cryptlib::interpolate_q(&points_q, &values_q) == f_q,
+        "interpolate_q"
+    );
+
+    // resultant by evaluation and interpolation against the Sylvester determinant
+    let to_bv = |coeffs: &[&[i64]]| -> Vec<Vec<Integer>> {
+        coeffs
+            .iter()
+            .map(|row| row.iter().map(|x| Integer::from(*x)).collect())
+            .collect()
+    };
+    let f_bv = to_bv(&[&[-5, 0, 0, 0], &[0, 0, 0, 0], &[0, 0, 0, 0], &[1, 0, 0, 0]]);
+    let g_bv = to_bv(&[&[-9, 0, 0, 1], &[0, 0, 3, 0], &[0, 3, 0, 0], &[1, 0, 0, 0]]);
+    // the x^2 coefficient of h vanishes at y = 0 and y = 1
+    let h_bv = to_bv(&[&[1, 2], &[0, -3, 1], &[0, -1, 1]]);
+    for (a, b) in [(&f_bv, &g_bv), (&g_bv, &h_bv), (&h_bv, &f_bv)] {
+        for modulus in [n.clone(), Integer::from(-1)] {
+            // the Sylvester layout in resultant carries a sign of (-1)^(deg f deg g)
+            let mut expected = cryptlib::resultant(a, b, &modulus);
+            if (a.len() - 1) * (b.len() - 1) % 2 == 1 {
+                expected = expected.into_iter().map(|x| -x).collect();
+            }
+            let result = cryptlib::resultant_interpolate(a, b, &modulus).unwrap();
+            let same = expected.iter().enumerate().all(|(i, coef)| {
+                let result_coef = result.get(i).cloned().unwrap_or_default();
+                if modulus > 0 {
+                    (coef - result_coef) % &modulus == 0
+                } else {
+                    *coef == result_coef
+                }
+            });
+            // resultant keeps only the first 10 coefficients
+            assert!(same, "resultant {:?} {:?}", expected, result);
+        }
+    }
+
+    println!("{:>6} {:>12} {:>12}", "points", "one by one", "multipoint");
+    for count in [64, 256, 1024, 4096] {
+        let f = random_poly(count, &n);
+        let points = random_poly(count, &n);
+        let now = Instant::now();
+        for point in &points {
+            cryptlib::eval_poly(point, &f, &n);
+        }
+        let single_time = now.elapsed();
+        let now = Instant::now();
+        cryptlib::eval_poly_multi(&f, &points, &n);
+        println!("{:>6} {:>12?} {:>12?}", count, single_time, now.elapsed());
+    }
+}
+
+fn test_multivariate() {
+    use cryptlib_mv::{MonomialOrder, PolyMv};
+    let term = |exponents: &[u32], c: i64| (exponents.to_vec(), Integer::from(c));
+
+    // the orders on 4xy^2z + 4z^2 - 5x^3 + 7x^2z^2 (Cox, Little, O'Shea 2.2)
+    let f = PolyMv::from_terms(
+        3,
+        vec![
+            term(&[1, 2, 1], 4),
+            term(&[0, 0, 2], 4),
+            term(&[3, 0, 0], -5),
+            term(&[2, 0, 2], 7),
+        ],
+    );
+    let expected = [
+        (
+            MonomialOrder::Lex,
+            [[3, 0, 0], [2, 0, 2], [1, 2, 1], [0, 0, 2]],
+        ),
+        (
+            MonomialOrder::Grlex,
+            [[2, 0, 2], [1, 2, 1], [3, 0, 0], [0, 0, 2]],
+        ),
+        (
+            MonomialOrder::Grevlex,
+            [[1, 2, 1], [2, 0, 2], [3, 0, 0], [0, 0, 2]],
+        ),
+    ];
+    for (order, monomials) in expected {
+        let sorted: Vec<Vec<u32>> = f
+            .terms_ordered(order)
+            .into_iter()
+            .map(|(m, _)| m.clone())
+            .collect();
+        assert!(sorted == monomials, "{:?} gave {:?}", order, sorted);
+    }
+    println!("f = {}", f);
+
+    // arithmetic and substitution agree with evaluation, mod n and over the integers
+    let mut rand = RandState::new();
+    let n = Integer::from(Integer::random_bits(256, &mut rand)) | 1;
+    let x = PolyMv::variable(3, 0);
+    let y = PolyMv::variable(3, 1);
+    let z = PolyMv::variable(3, 2);
+    let g = x
+        .mul(&y, &n)
+        .sub(&z.exp(&Integer::from(3), &n), &n)
+        .add(&PolyMv::constant(3, Integer::from(11)), &n);
+    let point: Vec<Integer> = (0..3)
+        .map(|_| Integer::from(n.random_below_ref(&mut rand)))
+        .collect();
+    for modulus in [n.clone(), Integer::from(-1)] {
+        let reduce = |val: Integer| {
+            if modulus > 0 {
+                ((val % &modulus) + &modulus) % &modulus
+            } else {
+                val
+            }
+        };
+        let f_val = f.eval(&point, &modulus);
+        let g_val = g.eval(&point, &modulus);
+        assert!(
+            f.mul(&g, &modulus).eval(&point, &modulus) == reduce(Integer::from(&f_val * &g_val))
+        );
+        assert!(
+            f.sub(&g, &modulus).eval(&point, &modulus) == reduce(Integer::from(&f_val - &g_val))
+        );
+        let f_cubed = f.exp(&Integer::from(3), &modulus);
+        assert!(f_cubed.eval(&point, &modulus) == reduce(f_val.clone().pow(3)));
+
+        // f(x, g, z) and f(x, y, 5)
+        let mut substituted_point = point.clone();
+        substituted_point[1] = g_val.clone();
+        assert!(
+            f.substitute(1, &g, &modulus).eval(&point, &modulus)
+                == f.eval(&substituted_point, &modulus)
+        );
+        let mut partial_point = point.clone();
+        partial_point[2] = Integer::from(5);
+        let partial = f.partial_eval(2, &Integer::from(5), &modulus);
+        assert!(partial.degree_in(2) == 0);
+        assert!(partial.eval(&point, &modulus) == f.eval(&partial_point, &modulus));
+    }
+
+    // round trip through the bivariate layout, and agreement with multiply_poly_bv
+    let f_bv: Vec<Vec<Integer>> = [vec![1, 433], vec![-28, 150]]
+        .iter()
+        .map(|row| row.iter().map(|val| Integer::from(*val)).collect())
+        .collect();
+    let f_mv = PolyMv::from_bv(&f_bv, 3, 0, 2);
+    assert!(f_mv.to_bv(0, 2) == f_bv);
+    let minus_one = Integer::from(-1);
+    let square = cryptlib_bv::multiply_poly_bv(&f_bv, &f_bv, &minus_one);
+    assert!(f_mv.mul(&f_mv, &minus_one).to_bv(0, 2) == square);
+    println!("(x, z) layout {:?} is {}", f_bv, f_mv);
+}
+
+fn test_groebner() {
+    use cryptlib_mv::{MonomialOrder, PolyMv};
+    let poly = |vars: usize, terms: &[(&[u32], i64)]| {
+        PolyMv::from_terms(
+            vars,
+            terms.iter().map(|(m, c)| (m.to_vec(), Integer::from(*c))),
+        )
+    };
+    let over_q = Integer::from(-1);
+
+    // x^3 - 2xy, x^2 y - 2y^2 + x in grlex give {x^2, xy, y^2 - x/2} (Cox, Little, O'Shea 2.7)
+    let f1 = poly(2, &[(&[3, 0], 1), (&[1, 1], -2)]);
+    let f2 = poly(2, &[(&[2, 1], 1), (&[0, 2], -2), (&[1, 0], 1)]);
+    let basis =
+        cryptlib_mv::groebner_basis(&[f1.clone(), f2.clone()], MonomialOrder::Grlex, &over_q);
+    let expected = vec![
+        poly(2, &[(&[0, 2], 2), (&[1, 0], -1)]),
+        poly(2, &[(&[1, 1], 1)]),
+        poly(2, &[(&[2, 0], 1)]),
+    ];
+    assert!(basis == expected, "grlex basis {:?}", basis);
+    for order in [MonomialOrder::Lex, MonomialOrder::Grevlex] {
+        let basis = cryptlib_mv::groebner_basis(&[f1.clone(), f2.clone()], order, &over_q);
+        for f in [&f1, &f2] {
+            assert!(cryptlib_mv::normal_form(f, &basis, order, &over_q).is_zero());
+        }
+    }
+
+    // the same ideal over GF(p) is monic, with y^2 - x/2 scaled by 2^-1
+    let p = Integer::from(1000003);
+    let basis = cryptlib_mv::groebner_basis(&[f1, f2], MonomialOrder::Grlex, &p);
+    let half = cryptlib::find_inverse(&Integer::from(2), &p);
+    assert!(basis[0].coefficient(&[1, 0]) == Integer::from(&p - &half));
+    assert!(basis
+        .iter()
+        .all(|g| g.leading_term(MonomialOrder::Grlex).unwrap().1 == &1));
+
+    // random quadratics in x, y, z vanishing at a common integer root
+    let mut rand = RandState::new();
+    let root = [Integer::from(3), Integer::from(-5), Integer::from(7)];
+    let mut polys = Vec::new();
+    for _ in 0..3 {
+        let mut terms = Vec::new();
+        for a in 0..=2u32 {
+            for b in 0..=(2 - a) {
+                for c in 0..=(2 - a - b) {
+                    let coef = Integer::from(Integer::random_bits(12, &mut rand)) - 2048;
+                    terms.push((vec![a, b, c], coef));
+                }
+            }
+        }
+        let f = PolyMv::from_terms(3, terms);
+        let value = f.eval(&root, &over_q);
+        polys.push(f.sub(&PolyMv::constant(3, value), &over_q));
+    }
+    let now = Instant::now();
+    let roots = cryptlib_mv::common_integer_roots(&polys);
+    println!("common roots {:?} in {:?}", roots, now.elapsed());
+    assert!(roots.contains(&root.to_vec()));
+    for found in &roots {
+        assert!(polys.iter().all(|f| f.eval(found, &over_q) == 0));
+    }
+}
+
+fn test_resultant_mv() {
+    use cryptlib_mv::PolyMv;
+    let poly = |vars: usize, terms: &[(&[u32], i64)]| {
+        PolyMv::from_terms(
+            vars,
+            terms.iter().map(|(m, c)| (m.to_vec(), Integer::from(*c))),
+        )
+    };
+    let over_z = Integer::from(-1);
+
+    // x^2 + y^2 - 25 and x - y - 1 meet where 2y^2 + 2y - 24 = 0
+    let circle = poly(2, &[(&[2, 0], 1), (&[0, 2], 1), (&[0, 0], -25)]);
+    let line = poly(2, &[(&[1, 0], 1), (&[0, 1], -1), (&[0, 0], -1)]);
+    let eliminated = cryptlib_mv::resultant_mv(&circle, &line, 0);
+    assert!(
+        eliminated == poly(2, &[(&[0, 2], 2), (&[0, 1], 2), (&[0, 0], -24)]),
+        "{}",
+        eliminated
+    );
+    let roots = cryptlib_mv::roots_by_resultants(&[circle.clone(), line.clone()]);
+    let expected: Vec<Vec<Integer>> = vec![
+        vec![Integer::from(-3), Integer::from(-4)],
+        vec![Integer::from(4), Integer::from(3)],
+    ];
+    assert!(roots == expected, "{:?}", roots);
+
+    // agrees with the bivariate resultant, here with a leading coefficient in x that vanishes at y = 0, 1
+    let h = poly(
+        2,
+        &[
+            (&[0, 0], 1),
+            (&[0, 1], 2),
+            (&[1, 1], -3),
+            (&[1, 2], 1),
+            (&[2, 1], -1),
+            (&[2, 2], 1),
+        ],
+    );
+    let bv = cryptlib::resultant_interpolate(&h.to_bv(0, 1), &circle.to_bv(0, 1), &over_z).unwrap();
+    let mv = cryptlib_mv::resultant_mv(&h, &circle, 0).to_univariate(1);
+    assert!(bv == mv, "{:?} {:?}", bv, mv);
+
+    // three variables, eliminating x then y from random quadratics sharing an integer root
+    let mut rand = RandState::new();
+    let root = vec![Integer::from(-12), Integer::from(31), Integer::from(5)];
+    let mut polys = Vec::new();
+    for _ in 0..3 {
+        let mut terms = Vec::new();
+        for a in 0..=2u32 {
+            for b in 0..=(2 - a) {
+                for c in 0..=(2 - a - b) {
+                    terms.push((
+                        vec![a, b, c],
+                        Integer::from(Integer::random_bits(40, &mut rand))
+                            - (Integer::from(1) << 39),
+                    ));
+                }
+            }
+        }
+        let f = PolyMv::from_terms(3, terms);
+        let value = f.eval(&root, &over_z);
+        polys.push(f.sub(&PolyMv::constant(3, value), &over_z));
+    }
+    let now = Instant::now();
+    let eliminated = cryptlib_mv::resultant_mv(&polys[0], &polys[1], 0);
+    assert!(eliminated.degree_in(0) == 0);
+    assert!(eliminated.eval(&root, &over_z) == 0);
+    let roots = cryptlib_mv::roots_by_resultants(&polys);
+    println!("roots {:?} in {:?}", roots, now.elapsed());
+    assert!(roots.contains(&root));
+
+    // no variables left to eliminate
+    let zero = PolyMv::constant(0, Integer::from(0));
+    let one = PolyMv::constant(0, Integer::from(1));
+    assert!(
+        cryptlib_mv::roots_by_resultants(std::slice::from_ref(&zero))
+            == vec![Vec::<Integer>::new()]
+    );
+    assert!(cryptlib_mv::roots_by_resultants(&[zero, one]).is_empty());
+
+    // an empty slice is the zero polynomial
+    let f = vec![Integer::from(-2), Integer::from(0), Integer::from(1)];
+    assert!(cryptlib::resultant_univariate(&[], &f) == 0);
+    assert!(cryptlib::resultant_univariate(&f, &[]) == 0);
+}
+
+fn test_manger() {
+    // FIPS 180 examples, the 448 bit message takes two blocks once padded
+    let two_blocks = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+    for (hash, msg, expected) in [
+        (
+            cryptlib_padding::Hash::Sha1,
+            &b"abc"[..],
+            "a9993e364706816aba3e25717850c26c9cd0d89d",
+        ),
+        (
+            cryptlib_padding::Hash::Sha256,
+            &b"abc"[..],
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        ),
+        (
+            cryptlib_padding::Hash::Sha1,
+            &two_blocks[..],
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1",
+        ),
+        (
+            cryptlib_padding::Hash::Sha256,
+            &two_blocks[..],
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+        ),
+    ] {
+        let expected = Integer::from_str_radix(expected, 16)
+            .unwrap()
+            .to_digits::<u8>(Order::Msf);
+        assert!(
+            hash.digest(msg) == expected,
+            "{:?} of {} bytes",
+            hash,
+            msg.len()
+        );
+    }
+
+    let mut rand = RandState::new();
+    let n_bits = 1024;
+    let e = Integer::from(65537);
+    let key = random_rsa_key(n_bits, &e, &mut rand);
+    let n = key.n.clone();
+
+    let msg = b"attack at dawn";
+    let label = b"orders";
+    for hash in [cryptlib_padding::Hash::Sha1, cryptlib_padding::Hash::Sha256] {
+        let c = cryptlib_padding::oaep_encrypt(&n, &e, msg, label, hash, &mut rand).unwrap();
+        assert!(cryptlib_padding::oaep_decrypt(&key, &c, label, hash).unwrap() == msg);
+        assert!(
+            cryptlib_padding::oaep_decrypt(&key, &c, b"other", hash)
+                == Err(cryptlib_padding::OaepError::Padding)
+        );
+
+        let mut oracle = cryptlib_padding::OaepOracle {
+            key: key.clone(),
+            label: label.to_vec(),
+            hash,
+            queries: 0,
+        };
+        let report = cryptlib_padding::manger(&n, &e, &c, &mut oracle).unwrap();
+        assert!(report.queries == oracle.queries);
+        assert!(report.queries < 2 * n_bits as usize);
+        let em = cryptlib_padding::to_bytes(&report.m, 128);
+        assert!(cryptlib_padding::oaep_decode(&em, label, hash).unwrap() == msg);
+        println!(
+            "{:?}: {} queries ({:?} in steps 1, 2, 3) for a {}-bit N",
+            hash,
+            report.queries,
+            report.step_queries,
+            n.significant_bits()
+        );
+    }
+}
+
+fn test_bleichenbacher() {
+    let mut rand = RandState::new();
+    let e = Integer::from(65537);
+    let key = random_rsa_key(256, &e, &mut rand);
+    let n = key.n.clone();
+
+    let msg = b"attack at dawn";
+    let c = cryptlib_padding::pkcs1_v15_encrypt(&n, &e, msg, &mut rand).unwrap();
+    assert!(cryptlib_padding::pkcs1_v15_decrypt(&key, &c).unwrap() == msg);
+    let em = cryptlib_padding::pkcs1_v15_pad(msg, 32, &mut rand).unwrap();
+    assert!(cryptlib_padding::pkcs1_v15_unpad(&em[1..]).is_none());
+
+    for strict in [false, true] {
+        let mut oracle = cryptlib_padding::Pkcs1Oracle {
+            key: key.clone(),
+            strict,
+            queries: 0,
+        };
+        let now = Instant::now();
+        let report = cryptlib_padding::bleichenbacher(&n, &e, &c, &mut oracle).unwrap();
+        assert!(report.queries == oracle.queries);
+        let em = cryptlib_padding::to_bytes(&report.m, 32);
+        assert!(cryptlib_padding::pkcs1_v15_unpad(&em).unwrap() == msg);
+        for step in report.steps.iter().take(4) {
+            println!(
+                "i = {}: {} queries, {} intervals, width 2^{:.1}",
+                step.i, step.queries, step.intervals, step.log2_width
+            );
+        }
+        println!(
+            "strict = {}: {} queries, {} steps, {:?}",
+            strict,
+            report.queries,
+            report.steps.len(),
+            now.elapsed()
+        );
+    }
+}
+
+fn test_crt_fault() {
+    let mut rand = RandState::new();
+    let e = Integer::from(65537);
+    let key = random_rsa_key(512, &e, &mut rand);
+    let (n, d) = (key.n.clone(), key.d.clone());
+
+    let m = Integer::from(n.random_below_ref(&mut rand));
+    let c = cryptlib::fast_power(&m, &e, &n);
+    assert!(key.decrypt_crt(&c) == m);
+
+    let mut signer = cryptlib_rsa::CrtSigner {
+        key: key.clone(),
+        fault: cryptlib_rsa::CrtFault::None,
+        verify_before_release: false,
+    };
+    let s = signer.sign(&m).unwrap();
+    assert!(s == cryptlib::fast_power(&m, &d, &n));
+
+    for fault in [
+        cryptlib_rsa::CrtFault::FlipSp(17),
+        cryptlib_rsa::CrtFault::FlipSq(200),
+    ] {
+        signer.fault = fault;
+        signer.verify_before_release = false;
+        let faulty_s = signer.sign(&m).unwrap();
+        let factors = Some((key.p.clone(), key.q.clone()));
+        assert!(cryptlib_rsa::lenstra_fault_attack(&n, &e, &m, &faulty_s) == factors);
+        assert!(cryptlib_rsa::bellcore_fault_attack(&n, &s, &faulty_s) == factors);
+        println!("{:?}: factored N", fault);
+
+        // verifying before release withholds the faulty signature
+        signer.verify_before_release = true;
+        assert!(signer.sign(&m).is_none());
+    }
+    assert!(cryptlib_rsa::lenstra_fault_attack(&n, &e, &m, &s).is_none());
+}
+
+fn test_factor_with_known_bits() {
+    let mut rand = RandState::new();
+    let n_bits = 512;
+    let key = random_rsa_key(n_bits, &Integer::from(65537), &mut rand);
+    let (n, p, q) = (key.n, key.p, key.q);
+    println!(
+        "at least {} known bits",
+        cryptlib_partial_key::min_known_bits(&n)
+    );
+    assert!(cryptlib_partial_key::min_known_bits(&n) == 128);
+
+    // unknown windows of 110 bits at the bottom, top and middle of p
+    let all_bits = (Integer::from(1) << (n_bits / 2)) - 1;
+    let window = (Integer::from(1) << 110) - 1;
+    for low in [0, n_bits / 2 - 110, 70] {
+        let mask = &all_bits ^ Integer::from(&window << low);
+        let known = Integer::from(&p & &mask);
+        let now = Instant::now();
+        let factors = cryptlib_partial_key::factor_with_known_bits(&n, &known, &mask);
+        println!("unknown bits {}..{} in {:?}", low, low + 110, now.elapsed());
+        assert!(factors == Some((p.clone(), q.clone())));
+    }
+
+    // two windows are not one small root
+    let mask = Integer::from(&all_bits ^ 0xff00ff);
+    let known = Integer::from(&p & &mask);
+    assert!(cryptlib_partial_key::factor_with_known_bits(&n, &known, &mask).is_none());
+}
+
+fn test_stereotyped_message() {
+    let mut rand = RandState::new();
+    // a key for e = 15 is a key for both e = 3 and e = 5
+    let n = random_rsa_key(512, &Integer::from(15), &mut rand).n;
+
+    let msg = "the password is SWORDFISH, bye".as_bytes();
+    let template = vec![
+        cryptlib_rsa::TemplatePart::Known(msg[..16].to_vec()),
+        cryptlib_rsa::TemplatePart::Unknown(72),
+        cryptlib_rsa::TemplatePart::Known(msg[25..].to_vec()),
+    ];
+    for e in [3, 5] {
+        let e = Integer::from(e);
+        let c = cryptlib::fast_power(&Integer::from_digits(msg, Order::Msf), &e, &n);
+        let now = Instant::now();
+        let recovered = cryptlib_rsa::stereotyped_message_attack(&n, &e, &c, &template).unwrap();
+        println!(
+            "e = {}: {} in {:?}",
+            e,
+            String::from_utf8(recovered.clone()).unwrap(),
+            now.elapsed()
+        );
+        assert!(recovered == msg);
+    }
+
+    // a second unknown part would need a multivariate method
+    let template = vec![
+        cryptlib_rsa::TemplatePart::Unknown(8),
+        cryptlib_rsa::TemplatePart::Known(msg[1..16].to_vec()),
+        cryptlib_rsa::TemplatePart::Unknown(72),
+        cryptlib_rsa::TemplatePart::Known(msg[25..].to_vec()),
+    ];
+    let c = cryptlib::fast_power(
+        &Integer::from_digits(msg, Order::Msf),
+        &Integer::from(3),
+        &n,
+    );
+    assert!(
+        cryptlib_rsa::stereotyped_message_attack(&n, &Integer::from(3), &c, &template)
+            == Err(cryptlib_rsa::StereotypedMessageError::UnknownParts(2))
+    );
+}
+
+fn test_factor_from_private_key() {
+    let mut rand = RandState::new();
+    let e = Integer::from(65537);
+    let generated = random_rsa_key(512, &e, &mut rand);
+    let (n, d) = (generated.n.clone(), generated.d.clone());
+    let p_minus_one = Integer::from(&generated.p - 1);
+    let q_minus_one = Integer::from(&generated.q - 1);
+    let phi = Integer::from(&p_minus_one * &q_minus_one);
+    let lambda = phi.clone() / cryptlib::gcd(&p_minus_one, &q_minus_one);
+
+    let key = cryptlib_rsa::factor_from_private_key(&n, &e, &d).unwrap();
+    assert!(Integer::from(&key.p * &key.q) == n && key.p > key.q);
+    assert!(key.dp == &d % Integer::from(&key.p - 1));
+    assert!(key.dq == &d % Integer::from(&key.q - 1));
+    assert!(Integer::from(&key.q * &key.q_inv) % &key.p == 1);
+    assert!(cryptlib_rsa::factor_from_private_key_small_e(&n, &e, &d) == Some(key.clone()));
+    assert!(key == generated);
+
+    // d mod λ(N) is enough for the randomized algorithm but not for the small e one
+    let d_lambda = cryptlib::find_inverse(&e, &lambda);
+    let key_lambda = cryptlib_rsa::factor_from_private_key(&n, &e, &d_lambda).unwrap();
+    assert!(key_lambda.p == key.p && key_lambda.q == key.q);
+    if d_lambda != d {
+        assert!(cryptlib_rsa::factor_from_private_key_small_e(&n, &e, &d_lambda).is_none());
+    }
+    println!("p = {}", key.p);
+    println!("q = {}", key.q);
+}
+
+fn test_common_modulus() {
+    let mut rand = RandState::new();
+    let n_bits = 512;
+    let n = random_rsa_key(n_bits, &Integer::from(65537), &mut rand).n;
+    let encrypt = |m: &Integer, e: u32| (e, cryptlib::fast_power(m, &Integer::from(e), &n));
+
+    let m = Integer::from(Integer::random_below_ref(&n, &mut rand));
+    let (e1, c1) = encrypt(&m, 65537);
+    let (e2, c2) = encrypt(&m, 3);
+    let recovered =
+        cryptlib_rsa::common_modulus_attack(&n, &Integer::from(e1), &c1, &Integer::from(e2), &c2);
+    assert!(recovered == Some(m.clone()));
+
+    // pairwise non-coprime exponents with gcd 1 overall
+    let ciphertexts: Vec<(Integer, Integer)> = [6, 10, 15]
+        .iter()
+        .map(|e| {
+            let (e, c) = encrypt(&m, *e);
+            (Integer::from(e), c)
+        })
+        .collect();
+    assert!(cryptlib_rsa::common_modulus_attack_multi(&n, &ciphertexts) == Some(m.clone()));
+
+    // gcd 2 gives m^2, whose square root is m when m^2 < N
+    let ciphertexts: Vec<(Integer, Integer)> = [6, 10]
+        .iter()
+        .map(|e| {
+            let (e, c) = encrypt(&m, *e);
+            (Integer::from(e), c)
+        })
+        .collect();
+    assert!(cryptlib_rsa::common_modulus_attack_multi(&n, &ciphertexts).is_none());
+    let small_m = Integer::from(Integer::random_bits(n_bits / 2 - 8, &mut rand));
+    let ciphertexts: Vec<(Integer, Integer)> = [6, 10]
+        .iter()
+        .map(|e| {
+            let (e, c) = encrypt(&small_m, *e);
+            (Integer::from(e), c)
+        })
+        .collect();
+    assert!(cryptlib_rsa::common_modulus_attack_multi(&n, &ciphertexts) == Some(small_m));
+    println!("recovered all messages");
+}
+
+fn test_wiener() {
+    // 415/93 = [4; 2, 6, 7]
+    let quotients = cryptlib::continued_fraction(&Integer::from(415), &Integer::from(93));
+    assert!(quotients == [4, 2, 6, 7]);
+    let convergents = cryptlib::convergents(&quotients);
+    let expected = [(4, 1), (9, 2), (58, 13), (415, 93)];
+    assert!(convergents
+        .iter()
+        .zip(expected)
+        .all(|((h, k), (eh, ek))| *h == eh && *k == ek));
+
+    let mut rand = RandState::new();
+    let key = random_rsa_key(512, &Integer::from(65537), &mut rand);
+    let (p, q) = (key.p, key.q);
+    // p and q 2^150 apart, N^0.29
+    let close_q: Integer = (Integer::from(1) << 150) + &p;
+    let close_q = close_q.next_prime();
+
+    // (q, bits of d, found by Wiener, found by de Weger)
+    for (q, d_bits, wiener_expected, de_weger_expected) in [
+        (&q, 125, true, true),
+        (&q, 140, false, false),
+        (&close_q, 190, false, true),
+    ] {
+        let n = Integer::from(&p * q);
+        let phi_n = Integer::from(&p - 1) * Integer::from(q - 1);
+        let mut d = Integer::new();
+        while cryptlib::gcd(&d, &phi_n) != 1 {
+            d.assign(Integer::random_bits(d_bits, &mut rand));
+            d.set_bit(d_bits - 1, true);
+        }
+        let e = cryptlib::find_inverse(&d, &phi_n);
+
+        let now = Instant::now();
+        let result = cryptlib_rsa::wiener(&n, &e);
+        println!(
+            "wiener, d of {} bits: {} in {:?}",
+            d_bits,
+            result.is_some(),
+            now.elapsed()
+        );
+        assert!(result.is_some() == wiener_expected);
+        let now = Instant::now();
+        let result = cryptlib_rsa::wiener_de_weger(&n, &e);
+        println!(
+            "de weger, d of {} bits: {} in {:?}",
+            d_bits,
+            result.is_some(),
+            now.elapsed()
+        );
+        assert!(result.is_some() == de_weger_expected);
+        if let Some((found_d, found_p, found_q)) = result {
+            assert!(found_d == d);
+            assert!(Integer::from(&found_p * &found_q) == n);
+        }
+    }
+}
+
+fn test_boneh_durfee() {
+    let mut rand = RandState::new();
+    let key = random_rsa_key(256, &Integer::from(65537), &mut rand);
+    let n = key.n;
+    let phi_n = Integer::from(&key.p - 1) * Integer::from(&key.q - 1);
+
+    // 67 bit d is N^0.262, past Wiener's N^0.25 but well short of N^0.284, which needs a much
+    // larger m than is practical here, so both lattices are only checked on this d
+    for lattice in [
+        cryptlib_rsa::BonehDurfeeLattice::Full,
+        cryptlib_rsa::BonehDurfeeLattice::Sublattice,
+    ] {
+        let d_bits = 67;
+        let mut d = Integer::new();
+        while cryptlib::gcd(&d, &phi_n) != 1 {
+            d.assign(Integer::random_bits(d_bits, &mut rand));
+            d.set_bit(d_bits - 1, true);
+        }
+        let e = cryptlib::find_inverse(&d, &phi_n);
+        let now = Instant::now();
+        let result = cryptlib_rsa::boneh_durfee(&n, &e, 0.265, 4, lattice);
+        println!(
+            "{:?} d of {} bits: {:?} in {:?}",
+            lattice,
+            d_bits,
+            result.is_some(),
+            now.elapsed()
+        );
+        let (found_d, found_p, found_q) = result.unwrap();
+        assert!(found_d == d);
+        assert!(Integer::from(&found_p * &found_q) == n);
+    }
+}
+
+fn test_coppersmith_bv_auto() {
+    let f: Vec<Vec<Integer>> = [vec![1, 433], vec![-28, 150]]
+        .iter()
+        .map(|row| row.iter().map(|val| Integer::from(*val)).collect())
+        .collect();
+    let report = cryptlib_bv::coppersmith_bv_auto(&f, &Integer::from(19), &Integer::from(17), 3);
+    for attempt in &report.attempts {
+        println!(
+            "k {} ω {} bound met {} estimated {:?} took {:?}",
+            attempt.plan.k,
+            attempt.plan.dimension,
+            attempt.plan.bound_met,
+            attempt.plan.estimated_time,
+            attempt.elapsed
+        );
+    }
+    // the bound needs k = 8 here, but LLL does better than the bound and k = 1 is enough
+    assert!(report.k_bound.is_none());
+    assert!(report.attempts.len() == 1);
+    let (x, y) = report.root.unwrap();
+    assert!(cryptlib_bv::eval_poly_bv(&f, &x, &y, &Integer::from(-1)) == 0);
+
+    // large coefficients against small bounds meet Coron's bound once ε = 1/k is small enough
+    let mut rand = RandState::new();
+    let (x0, y0) = (Integer::from(11), Integer::from(-6));
+    let b = Integer::from(Integer::random_bits(40, &mut rand));
+    let c = Integer::from(Integer::random_bits(40, &mut rand));
+    let d = Integer::from(Integer::random_bits(40, &mut rand));
+    let a = -(b.clone() * &x0 + c.clone() * &y0 + d.clone() * &x0 * &y0);
+    let f = vec![vec![a, c], vec![b, d]];
+    let bound = Integer::from(16);
+    let plans: Vec<cryptlib_bv::CoronPlan> = (1..=3)
+        .map(|k| cryptlib_bv::coron_plan(&f, &bound, &bound, k))
+        .collect();
+    for plan in &plans {
+        println!("{:?}", plan);
+    }
+    assert!(
+        plans
+            .iter()
+            .map(|plan| plan.bound_met)
+            .collect::<Vec<bool>>()
+            == [false, false, true]
+    );
+    assert!(
+        plans
+            .iter()
+            .map(|plan| plan.dimension)
+            .collect::<Vec<usize>>()
+            == [9, 16, 25]
+    );
+    assert!(plans[0].estimated_time < plans[1].estimated_time);
+
+    // with k capped below the bound it still tries k = 1
+    let now = Instant::now();
+    let report = cryptlib_bv::coppersmith_bv_auto(&f, &bound, &bound, 1);
+    println!("{:?} in {:?}", report.root, now.elapsed());
+    assert!(report.k_bound.is_none());
+    assert!(report.root == Some((x0.clone(), y0.clone())));
+
+    // otherwise it starts at the first k meeting the bound
+    let now = Instant::now();
+    let report = cryptlib_bv::coppersmith_bv_auto(&f, &bound, &bound, 4);
+    println!("{:?} in {:?}", report.root, now.elapsed());
+    assert!(report.k_bound == Some(3));
+    assert!(report.attempts[0].plan.k == 3);
+    assert!(report.root == Some((x0, y0)));
+}
+
+fn test_coppersmith_bv_degrees() {
+    let over_z = Integer::from(-1);
+    let to_poly = |rows: Vec<Vec<i64>>| -> Vec<Vec<Integer>> {
+        rows.iter()
+            .map(|row| row.iter().map(|val| Integer::from(*val)).collect())
+            .collect()
+    };
+
+    // degree (2, 1) with rows of different lengths, constant term chosen to vanish at the root
+    let (x0, y0) = (Integer::from(2), Integer::from(-3));
+    let mut f = to_poly(vec![vec![0, 290017], vec![-230011], vec![70001, 50023]]);
+    f[0][0] = -cryptlib_bv::eval_poly_bv(&f, &x0, &y0, &over_z);
+    let mut cases = vec![(f, x0, y0, 1)];
+
+    // degree 2 with no constant term: x0 g(x, y) - g(x0, y0) x vanishes at (x0, y0), and the
+    // trivial root (0, 0) of every such f is skipped
+    let (x0, y0) = (Integer::from(3), Integer::from(1));
+    let g = to_poly(vec![
+        vec![0, 0, 110023],
+        vec![0, 170081],
+        vec![-130003, 0, 30011],
+    ]);
+    let mut f: Vec<Vec<Integer>> = g
+        .iter()
+        .map(|row| row.iter().map(|elem| Integer::from(elem * &x0)).collect())
+        .collect();
+    f[1][0] -= cryptlib_bv::eval_poly_bv(&g, &x0, &y0, &over_z);
+    cases.push((f, x0, y0, 1));
+
+    // degree 3, sparse with an empty row
+    let (x0, y0) = (Integer::from(-3), Integer::from(2));
+    let mut f = to_poly(vec![
+        vec![0, 0, 0, 50021],
+        vec![0, 70019],
+        vec![],
+        vec![20011],
+    ]);
+    f[0][0] = -cryptlib_bv::eval_poly_bv(&f, &x0, &y0, &over_z);
+    cases.push((f, x0, y0, 1));
+
+    for (f, x0, y0, k) in cases {
+        assert!(cryptlib_bv::eval_poly_bv(&f, &x0, &y0, &over_z) == 0);
+        let bound = Integer::from(4);
+        let now = Instant::now();
+        let (x, y) = cryptlib_bv::coppersmith_bv(&f, &bound, &bound, k).unwrap();
+        println!("{:?}: ({}, {}) in {:?}", f, x, y, now.elapsed());
+        assert!(cryptlib_bv::eval_poly_bv(&f, &x, &y, &over_z) == 0);
+        assert!(x.clone().abs() <= bound && y.clone().abs() <= bound);
+        assert!((x, y) == (x0, y0));
+        assert!(cryptlib_bv::coppersmith_bv(&f, &bound, &bound, 0).is_none());
+        assert!(!cryptlib_bv::coron_plan(&f, &bound, &bound, 0).bound_met);
+    }
+}
+
 fn test_coppersmith_bv() {
     let f: Vec<Vec<Integer>> = vec![vec![1, 433], vec![-28, 150]]
         .iter()
@@ -100,11 +1400,6 @@ fn test_partial_key() {
     let extra_bits = 14;// 1024:54, 512:28, 256:14, 128:8
     let n_bits = 256;
 
-    // let p = Integer::from(4013);
-    // let q = Integer::from(3851);
-    // let p = Integer::from(2703229499i64);
-    // let q = Integer::from(3240128051i64);
-
     let e = Integer::from(3);
 
     let mut p = Integer::new();
@@ -119,129 +1414,76 @@ fn test_partial_key() {
     while q.is_probably_prime(40) == IsPrime::No || Integer::from((q.clone() - 1) % &e) == 0 {
         q.assign(Integer::random_bits(n_bits / 2, &mut rand));
     }
+    if p < q {
+        std::mem::swap(&mut p, &mut q);
+    }
 
     let n = Integer::from(&p * &q);
     let phi_n = (p.clone() - 1) * (q.clone() - 1);
     let d = cryptlib::find_inverse(&e, &phi_n);
 
-    let mut n_len = 0;
-    let mut tmp = n.clone();
-    while tmp > 0 {
-        tmp >>= 1;
-        n_len += 1;
-    }
-    let mask_len = if n_len % 4 == 0 {
-        n_len / 4 + extra_bits
-    } else {
-        n_len / 4 + 1 + extra_bits
-    };
+    let n_len = n.significant_bits();
+    let mask_len = n_len.div_ceil(4) + extra_bits;
     let mask = (Integer::from(1) << mask_len) - 1;
-    let p0: Integer = p.clone() & &mask;
-    let q0: Integer = q.clone() & &mask;
-    let n0: Integer = n.clone() & &mask;
     let d0: Integer = d.clone() & &mask;
-    let ed0: Integer = (e.clone() * &d0) & &mask;
 
-    let now = Instant::now();
-
-    println!("p: {:x} ({:x})\nq: {} ({})", p, p0, q, q0);
+    println!("p: {:x}\nq: {:x}", p, q);
     println!("n: {:x} (len {})", n, n_len);
     println!("masklen: {}", mask_len);
-    println!("n0 {:x}", n0);
     println!("d: {:x} ({:x})", d, d0);
-    println!("ed mod 2^(n/4): {:x}", ed0);
-
-    let mut p_candidates = Vec::new();
-
-    for k in 0..(e.to_i32().unwrap()) {
-        let mut f = Vec::new();
-        f.push(n.clone() * k);
-        f.push(ed0.clone() - Integer::from(&n * k) - k - 1);
-        f.push(Integer::from(k));
-
-        let modulus = Integer::from(2).pow(mask_len);
-        let a = ((Integer::from(&f[2]) % &modulus) + &modulus) % &modulus;
-        let b = ((Integer::from(&f[1]) % &modulus) + &modulus) % &modulus;
-        let c = ((Integer::from(&f[0]) % &modulus) + &modulus) % &modulus;
-        println!(
-            " k: {}  {}x^2 + {}x + {} = 0 (mod 2^{})",
-            k, a, b, c, mask_len
-        );
-        let mut candidates = cryptlib::solve_quadratic(&a, &b, &c, mask_len);
-        candidates.sort();
-
-        for candidate in candidates {
-            let compliment = (n.clone()
-                * cryptlib::find_inverse(&candidate, &Integer::from(2).pow(mask_len)))
-                & &mask;
-            let p_candidate = std::cmp::min(candidate, compliment);
-            if !p_candidates.contains(&p_candidate) {
-                p_candidates.push(p_candidate);
-            }
-        }
-        println!(" p candidates {:?}", p_candidates);
-    }
-    // let l_correct = Float::with_val(128, &p).log2().ceil().to_integer().unwrap();
-    // let k_correct: Float = Float::with_val(128, &n).log2() / 4;
-    // let k_correct: Integer = k_correct.floor().to_integer().unwrap() + 1 + extra_bits;
-    // println!("k correct {}  l correct {}", k_correct, l_correct);
-    // let correct_x = p.clone() / Integer::from(2).pow(k_correct.to_u32().unwrap());
-    // let correct_y = q.clone() / Integer::from(2).pow(k_correct.to_u32().unwrap());
-    // println!("correct x {} correct y {}", correct_x, correct_y);
-
-    let mut p_guess: Option<Integer> = None;
-    let mut q_guess: Option<Integer> = None;
-    'l_search: for l_offset in [0, 1, -1, 2, -2, 3, -3, 4, -4, 5, -5, 6, -6, 7, -7, 8, -8] {
-        let l = (n_len as i32 / 2 + l_offset) as u32;
-        println!("trying l {}", l);
-        for (i, p0_guess) in p_candidates.iter().enumerate() {
-            // let p0_guess = p0.clone();
-            let q0_guess: Integer = (n.clone()
-                * cryptlib::find_inverse(&p0_guess, &Integer::from(2).pow(mask_len)))
-                & &mask;
-            println!(" trying {} p0: {:x} q0: {:x}", i, p0_guess, q0_guess);
-
-            let k = (n_len / 4) + 1 + extra_bits;
-            let cap_x = Integer::from(2).pow(l - k);
-            let cap_y = n.clone() / Integer::from(2).pow(l + k - 1);
 
-            let mut f = Vec::new();
-            let mut row = Vec::new();
-            row.push((p0_guess.clone() * q0_guess.clone() - n.clone()) / Integer::from(2).pow(k));
-            row.push(p0_guess.clone());
-            f.push(row);
-            let mut row = Vec::new();
-            row.push(q0_guess.clone());
-            row.push(Integer::from(2).pow(k));
-            f.push(row);
+    // low bits of d, e = 3
+    let now = Instant::now();
+    let key = cryptlib_partial_key::partial_key_d_lsb(&n, &e, &d0, mask_len).unwrap();
+    let duration = now.elapsed();
+    println!("recovered d {:x}", key.d);
+    assert!(key.d == d && key.p == p && key.q == q);
+    let mins = duration.as_secs() / 60;
+    let secs = duration.as_secs() % 60;
+    println!("in {} minutes {} seconds", mins, secs);
 
-            if let Some((x0, y0)) = cryptlib_bv::coppersmith_bv(&f, &cap_x, &cap_y, 1) {
-                println!("  x0 {} y0 {}", x0, y0);
-                p_guess = Some(Integer::from(2).pow(k) * x0 + p0_guess);
-                q_guess = Some(Integer::from(2).pow(k) * y0 + &q0_guess);
-                println!(
-                    "\n  found p: {:x}\n        q: {:x}",
-                    p_guess.as_ref().unwrap(),
-                    q_guess.as_ref().unwrap()
-                );
-                if Integer::from(p_guess.as_ref().unwrap() * q_guess.as_ref().unwrap()) == n {
-                    break 'l_search;
-                }
-            }
-        }
+    // low and high bits of p
+    let now = Instant::now();
+    let p0: Integer = p.clone() & &mask;
+    assert!(
+        cryptlib_partial_key::partial_key_p_lsb(&n, &p0, mask_len) == Some((p.clone(), q.clone()))
+    );
+    let unknown_bits = n_len / 4 - extra_bits;
+    let p_msb: Integer = p.clone() >> unknown_bits;
+    assert!(
+        cryptlib_partial_key::partial_key_p_msb(&n, &p_msb, unknown_bits)
+            == Some((p.clone(), q.clone()))
+    );
+    println!("p from low and high bits in {:?}", now.elapsed());
+
+    // high bits of d for a prime e above N^(1/4)
+    let mut e = Integer::from(Integer::random_bits(100, &mut rand)).next_prime();
+    while cryptlib::gcd(&e, &phi_n) != 1 {
+        e.next_prime_mut();
     }
-
-    if let (Some(recovered_p), Some(recovered_q)) = (p_guess, q_guess) {
-        let recovered_phi = (recovered_p.clone() - 1) * (recovered_q.clone() - 1);
-        let recovered_d = cryptlib::find_inverse(&e, &recovered_phi);
-        let duration = now.elapsed();
-        println!("recovered d {:x}", recovered_d);
-        assert!(recovered_d == d);
-        let mins = duration.as_secs() / 60;
-        let secs = duration.as_secs() % 60;
-        println!("in {} minutes {} seconds", mins, secs);
-    } else {
-        println!("failed");
+    let d = cryptlib::find_inverse(&e, &phi_n);
+    let unknown_bits = n_len - e.significant_bits() - 8;
+    let d_msb: Integer = d.clone() >> unknown_bits;
+    let now = Instant::now();
+    let key = cryptlib_partial_key::partial_key_d_msb(&n, &e, &d_msb, unknown_bits).unwrap();
+    assert!(key.d == d && key.p == p && key.q == q);
+    println!(
+        "d from {} high bits in {:?}",
+        n_len - unknown_bits,
+        now.elapsed()
+    );
+
+    // the first success in task order is returned whatever the thread count
+    let tasks: Vec<u32> = (0..1000).collect();
+    for threads in [1, 4, 16] {
+        let found = cryptlib_partial_key::parallel_search(&tasks, threads, |x| {
+            if x % 7 == 3 && *x > 100 {
+                Some(x * 2)
+            } else {
+                None
+            }
+        });
+        assert!(found == Some((101, 202)));
     }
 }
 
@@ -268,74 +1510,20 @@ fn test_short_pad() {
     let m = Integer::from_digits("YELLOW SUBMARINE".as_bytes(), Order::Lsf);
     // let m = Integer::from(211601);
 
-    let m1 = (m.clone() << 32) + Integer::from(12461);
-    let m2 = (m.clone() << 32) + Integer::from(28911);
-    let diff = Integer::from(&m2 - &m1);
+    // pads of up to 24 bits, m2 < m1 so that Δ = m2 - m1 is negative
+    let pad_bits = 24;
+    let m1 = (m.clone() << pad_bits) + Integer::from(12461247);
+    let m2 = (m.clone() << pad_bits) + Integer::from(2891);
 
     let c1 = cryptlib::fast_power(&m1, &e, &n);
     let c2 = cryptlib::fast_power(&m2, &e, &n);
 
-    let mut g1: Vec<Vec<Integer>> = (0..4)
-        .map(|_x| (0..4).map(|_y| Integer::from(0)).collect())
-        .collect();
-    g1[0][0] -= &c1; // x^3 - c1
-    g1[3][0] += 1;
-
-    let mut g2: Vec<Vec<Integer>> = (0..4)
-        .map(|_x| (0..4).map(|_y| Integer::from(0)).collect())
-        .collect();
-    g2[0][0] -= &c2; // (x+y)^3 - c2
-    g2[0][3] += 1;
-    g2[1][2] += 3;
-    g2[2][1] += 3;
-    g2[3][0] += 1;
-
-    println!("{:?}", g1);
-    println!("{:?}", g2);
-    println!();
-    let resultant = cryptlib::resultant(&g1, &g2, &n);
-    println!("{:?}", resultant);
-
-    let inverted = resultant.iter().map(|x| Integer::from(-x)).collect();
-    let result_inverted = cryptlib::eval_poly(&diff, &inverted, &n);
-    println!("sanity: resultant({}) = {}", diff, result_inverted);
-    let delta = cryptlib::coppersmith(&inverted, &n, 1, 18).unwrap();
-    println!("delta {}", delta);
-
-    let mut f = Vec::new();
-    f.push(-delta);
-    f.push(Integer::from(1));
-
-    let mut fr_g1 = cryptlib::exp_poly(&f, &e);
-    fr_g1[0] -= &c1;
-
-    for val in fr_g1.iter_mut() {
-        *val %= &n;
-        *val += &n;
-        *val %= &n;
-    }
-    let mut identity_func = Vec::new();
-    identity_func.push(Integer::from(0));
-    identity_func.push(Integer::from(1));
-    let mut fr_g2 = cryptlib::exp_poly(&identity_func, &e);
-    fr_g2[0] -= &c2;
-    for val in fr_g2.iter_mut() {
-        *val %= &n;
-        *val += &n;
-        *val %= &n;
-    }
-
-    println!("g1: {:?}\ng2: {:?}", fr_g1, fr_g2);
-
-    let (mut r, _s, _t) = cryptlib::poly_extended_euclidean_zn(&fr_g1, &fr_g2, &n);
-    // println!("r: {:?}\ns: {:?}\nt: {:?}", r, s, t);
-
-    r[0] += &n;
-    println!("r {:?}", r);
-    let inv_x_term = cryptlib::find_inverse(&r[1], &n);
-    let recovered_m2: Integer = (((Integer::from(&inv_x_term * -1) * &r[0] % &n) + &n) % &n) >> 32;
-
-    println!("recovered m2: {}", recovered_m2);
+    assert!(cryptlib_rsa::short_pad_in_bound(&n, &e, pad_bits));
+    assert!(!cryptlib_rsa::short_pad_in_bound(&n, &e, 57));
+    let now = Instant::now();
+    let recovered_m2 = cryptlib_rsa::short_pad_attack(&n, &e, &c1, &c2, pad_bits).unwrap();
+    println!("recovered m2: {} in {:?}", recovered_m2, now.elapsed());
+    assert!(recovered_m2 == m);
 
     let mut msg_bytes = Vec::new();
     for offset in 0..=(recovered_m2.significant_bits() / 8) {
@@ -407,7 +1595,7 @@ fn test_div_poly_zn() {
     g.push(Integer::from(0));
     g.push(Integer::from(3));
 
-    let (mut r, q) = cryptlib::divide_poly_zn(&f, &g, &n);
+    let (mut r, q) = cryptlib::divide_poly_zn(&f, &g, &n).unwrap();
 
     for elem in r.iter_mut() {
         *elem += &n;
@@ -469,7 +1657,7 @@ fn test_poly_euclid() {
     println!("g1: {:?}", g1);
     println!("g2: {:?}", g2);
 
-    let (r, s, t) = cryptlib::poly_extended_euclidean_zn(&g1, &g2, &n);
+    let (r, s, t) = cryptlib::poly_extended_euclidean_zn(&g1, &g2, &n).unwrap();
     println!("{:?}", r);
     println!("{:?}", s);
     println!("{:?}", t);
@@ -516,31 +1704,8 @@ fn test_franklin_reiter() {
 
     println!("c1: {}\nc2: {}", c1, c2);
 
-    let mut g1 = cryptlib::exp_poly(&f, &e);
-    g1[0] -= &c1;
-    for val in g1.iter_mut() {
-        *val %= &n;
-        *val += &n;
-        *val %= &n;
-    }
-    let mut identity_func = Vec::new();
-    identity_func.push(Integer::from(0));
-    identity_func.push(Integer::from(1));
-    let mut g2 = cryptlib::exp_poly(&identity_func, &e);
-    g2[0] -= &c2;
-    for val in g2.iter_mut() {
-        *val %= &n;
-        *val += &n;
-        *val %= &n;
-    }
-
-    println!("g1: {:?}\ng2: {:?}", g1, g2);
-
-    let (r, _s, _t) = cryptlib::poly_extended_euclidean_zn(&g1, &g2, &n);
-    // println!("r: {:?}\ns: {:?}\nt: {:?}", r, s, t);
-
-    let inv_x_term = cryptlib::find_inverse(&r[1], &n);
-    let recovered_m2 = Integer::from(&inv_x_term * -1) * &r[0] % &n;
+    let (recovered_m1, recovered_m2) = cryptlib_rsa::franklin_reiter(&n, &e, &c1, &c2, &f).unwrap();
+    assert!(recovered_m1 == msg1 && recovered_m2 == msg2);
 
     println!("recovered m2: {}", recovered_m2);
 
@@ -551,6 +1716,41 @@ fn test_franklin_reiter() {
         msg_bytes.push(low_u8);
     }
     println!("{}", String::from_utf8(msg_bytes).unwrap());
+
+    // a quadratic relation with e = 5
+    let e5 = Integer::from(5);
+    let quadratic = vec![Integer::from(-7), Integer::from(1), Integer::from(2)];
+    let msg1 = cryptlib::eval_poly(&msg2, &quadratic, &n);
+    let c1 = cryptlib::fast_power(&msg1, &e5, &n);
+    let c2 = cryptlib::fast_power(&msg2, &e5, &n);
+    assert!(
+        cryptlib_rsa::franklin_reiter(&n, &e5, &c1, &c2, &quadratic) == Ok((msg1, msg2.clone()))
+    );
+
+    // e = 65537, where the polynomials have degree e, over a 512-bit N to keep it quick
+    let e65537 = Integer::from(65537);
+    let n_512 = Integer::from(Integer::random_bits(256, &mut rand)).next_prime()
+        * Integer::from(Integer::random_bits(256, &mut rand)).next_prime();
+    let short_msg2 = Integer::from_digits("a shorter message".as_bytes(), Order::Lsf);
+    let msg1 = cryptlib::eval_poly(&short_msg2, &f, &n_512);
+    let c1 = cryptlib::fast_power(&msg1, &e65537, &n_512);
+    let c2 = cryptlib::fast_power(&short_msg2, &e65537, &n_512);
+    let now = Instant::now();
+    assert!(cryptlib_rsa::franklin_reiter(&n_512, &e65537, &c1, &c2, &f) == Ok((msg1, short_msg2)));
+    println!("e = {} in {:?}", e65537, now.elapsed());
+
+    // m1 = -m2 makes f(x)^3 - c1 a multiple of x^3 - c2, and a wrong relation leaves no common root
+    let negate = vec![Integer::from(0), Integer::from(-1)];
+    let c2 = cryptlib::fast_power(&msg2, &e, &n);
+    let c1 = Integer::from(&n - &c2);
+    assert!(
+        cryptlib_rsa::franklin_reiter(&n, &e, &c1, &c2, &negate)
+            == Err(cryptlib_rsa::FranklinReiterError::NonLinearGcd(3))
+    );
+    assert!(
+        cryptlib_rsa::franklin_reiter(&n, &e, &c1, &c2, &f)
+            == Err(cryptlib_rsa::FranklinReiterError::NonLinearGcd(0))
+    );
 }
 
 fn test_gsp_equivalence() {
@@ -702,111 +1902,48 @@ fn test_coppersmith() {
 }
 
 fn test_hastad_broadcast() {
-    struct HastadRSAConfig {
-        n: Integer,
-        f: Vec<Integer>,
-    }
-
     let n_bits = 256;
-    let num_configs = 3; // degree (x+c)^3 = 3
-    let m = 2;
-    let epsilon_denom = 11;
-    let e = Integer::from(3);
     let msg = Integer::from_digits("YELLOW SUBMARINE".as_bytes(), Order::Lsf);
-
-    let mut configs: Vec<HastadRSAConfig> = Vec::new();
+    println!("msg:{}", msg);
 
     let mut rand = RandState::new();
     // rand.seed(&Integer::from(1));
-    for i in 0..num_configs {
+    let mut moduli: Vec<Integer> = Vec::new();
+    for _ in 0..4 {
         let mut p = Integer::new();
         let mut q = Integer::new();
 
-        while p.is_probably_prime(40) == IsPrime::No
-            || Integer::from(&p % &e) == 0
-            || configs.iter().any(|config| &config.n % p.clone() == 0)
-        {
+        while p.is_probably_prime(40) == IsPrime::No || moduli.iter().any(|n| n.is_divisible(&p)) {
             p.assign(Integer::random_bits(n_bits / 2, &mut rand));
         }
 
-        while q.is_probably_prime(40) == IsPrime::No
-            || Integer::from(&q % &e) == 0
-            || configs.iter().any(|config| &config.n % q.clone() == 0)
-        {
+        while q.is_probably_prime(40) == IsPrime::No || moduli.iter().any(|n| n.is_divisible(&q)) {
             q.assign(Integer::random_bits(n_bits / 2, &mut rand));
         }
         let n = Integer::from(&p * &q);
         println!("p:{} q:{}\nn:{}\n", p, q, n);
-
-        let mut f = Vec::new();
-
-        f.push(Integer::from(32 * i));
-        f.push(Integer::from(1));
-        // println!("f: {:?}\n", f);
-
-        let config = HastadRSAConfig { n: n, f: f };
-
-        configs.push(config);
+        moduli.push(n);
     }
 
-    println!("msg:{}", msg);
-
-    let cs: Vec<Integer> = configs
-        .iter()
-        .map(|config| {
-            let f_x = cryptlib::eval_poly(&msg, &config.f, &config.n);
-            cryptlib::fast_power(&f_x, &e, &config.n)
-        })
-        .collect();
-
-    let gs: Vec<Vec<Integer>> = configs
-        .iter()
-        .zip(&cs)
-        .map(|(config, c)| {
-            let mut g = cryptlib::exp_poly(&config.f, &e);
-            g[0] -= c;
-            g
-        })
-        .collect();
-
-    let n1s: Vec<Integer> = (0..num_configs)
-        .map(|i| {
-            (&configs[0..i])
-                .iter()
-                .chain(&configs[i + 1..num_configs])
-                .fold(Integer::from(1), |acc, config| acc * &config.n)
-        })
-        .collect();
-
-    let ts: Vec<Integer> = configs
-        .iter()
-        .zip(&n1s)
-        .map(|(config, n1)| {
-            let (m1, _m2) = cryptlib::bezout(n1, &config.n);
-            Integer::from(n1 * m1)
-        })
-        .collect();
-
-    let n = Integer::from(&n1s[0] * &configs[0].n);
-    let mut g = Vec::new();
-    for _ in 0..(e.to_i32().unwrap() + 1) {
-        g.push(Integer::from(0));
-    }
-
-    for (g_partial, t) in gs.iter().zip(ts.iter()) {
-        for i in 0..g_partial.len() {
-            g[i] += Integer::from(&g_partial[i] * t);
-            g[i] %= &n;
+    let encrypt = |msg: &Integer, n: &Integer, e: u32, f: Vec<Integer>| {
+        let e = Integer::from(e);
+        let f_x = cryptlib::eval_poly(msg, &f, n);
+        let c = cryptlib::fast_power(&f_x, &e, n);
+        cryptlib_rsa::BroadcastInstance {
+            n: n.clone(),
+            e,
+            c,
+            f,
         }
-    }
-
-    println!("overall:");
-    println!("n:{}", n);
-    println!("g: {:?}", g);
-    println!("sanity: {}", cryptlib::eval_poly(&msg, &g, &n));
+    };
+    let linear = |a: i32, b: i32| vec![Integer::from(b), Integer::from(a)];
 
+    // e = 3 and pads x + 32i, three instances (degree 3)
+    let instances: Vec<cryptlib_rsa::BroadcastInstance> = (0..3)
+        .map(|i| encrypt(&msg, &moduli[i], 3, linear(1, 32 * i as i32)))
+        .collect();
     let now = Instant::now();
-    let x_0 = cryptlib::coppersmith(&g, &n, m, epsilon_denom).unwrap();
+    let x_0 = cryptlib_rsa::hastad_broadcast(&instances, 2, 11).unwrap();
     let duration = now.elapsed();
     println!("{}", x_0);
     let mut msg_bytes = Vec::new();
@@ -819,6 +1956,25 @@ fn test_hastad_broadcast() {
     let mins = duration.as_secs() / 60;
     let secs = duration.as_secs() % 60;
     println!("in {} minutes {} seconds", mins, secs);
+
+    // no padding, with a larger e among the instances: three e = 3 give m^3 over Z
+    let instances: Vec<cryptlib_rsa::BroadcastInstance> = (0..4)
+        .map(|i| encrypt(&msg, &moduli[i], if i == 1 { 5 } else { 3 }, linear(1, 0)))
+        .collect();
+    let now = Instant::now();
+    assert!(cryptlib_rsa::hastad_broadcast(&instances, 2, 11) == Some(msg.clone()));
+    println!("no padding in {:?}", now.elapsed());
+
+    // different exponents and non-monic pads, shifted up to degree 5, on a shorter message
+    let short_msg = Integer::from_digits("YELLOW".as_bytes(), Order::Lsf);
+    let instances = vec![
+        encrypt(&short_msg, &moduli[0], 3, linear(3, 7)),
+        encrypt(&short_msg, &moduli[1], 5, linear(2, -1)),
+        encrypt(&short_msg, &moduli[2], 3, linear(1, 1 << 20)),
+    ];
+    let now = Instant::now();
+    assert!(cryptlib_rsa::hastad_broadcast(&instances, 2, 8) == Some(short_msg));
+    println!("mixed exponents in {:?}", now.elapsed());
 }
 
 fn test_lll() {
@@ -844,6 +2000,31 @@ fn test_lll() {
     println!("{:?}", basis);
     let reduced = cryptlib::lll(&basis);
     println!("lll:\n{:?}", reduced);
+
+    // dependent vectors are reduced to 0 and dropped, leaving a basis of the same lattice
+    let to_basis = |data: Vec<Vec<i64>>| -> Vec<Vec<Integer>> {
+        return data
+            .iter()
+            .map(|row| row.iter().map(|val| Integer::from(*val)).collect())
+            .collect();
+    };
+    let dependent = to_basis(vec![vec![1, 0, 3], vec![0, 1, 5], vec![2, -3, -9]]);
+    let (reduced, _) = cryptlib::lll(&dependent);
+    println!("lll with a dependent vector:\n{:?}", reduced);
+    assert!(reduced.len() == 2);
+
+    // {2, 3} spans Z, not 2Z
+    let (reduced, _) = cryptlib::lll(&to_basis(vec![vec![2], vec![3]]));
+    println!("lll of [2], [3]:\n{:?}", reduced);
+    assert!(reduced == to_basis(vec![vec![1]]) || reduced == to_basis(vec![vec![-1]]));
+
+    // Z x 5Z from three vectors
+    let (reduced, _) = cryptlib::lll(&to_basis(vec![vec![2, 0], vec![3, 0], vec![4, 5]]));
+    println!("lll of [2, 0], [3, 0], [4, 5]:\n{:?}", reduced);
+    assert!(reduced.len() == 2);
+    let det = Integer::from(&reduced[0][0] * &reduced[1][1])
+        - Integer::from(&reduced[0][1] * &reduced[1][0]);
+    assert!(det.abs() == 5);
 }
 
 fn test_gsp() {
@@ -947,6 +2128,28 @@ fn test_rsa() {
     println!("recovered message {:?}", recovered_msg);
 }
 
+/// A random key with an N of n_bits, from primes of n_bits / 2 bits with the top bit set and
+/// gcd(p - 1, e) = gcd(q - 1, e) = 1, so that e is invertible mod φ(N)
+fn random_rsa_key(n_bits: u32, e: &Integer, rand: &mut RandState) -> cryptlib_rsa::RsaPrivateKey {
+    let mut random_prime = |other: &Integer| -> Integer {
+        let mut p = Integer::new();
+        while p.is_probably_prime(40) == IsPrime::No
+            || p == *other
+            || cryptlib::gcd(&Integer::from(&p - 1), e) != 1
+        {
+            p.assign(Integer::random_bits(n_bits / 2, rand));
+            p.set_bit(n_bits / 2 - 1, true);
+        }
+        return p;
+    };
+    let p = random_prime(&Integer::new());
+    let q = random_prime(&p);
+    let n = Integer::from(&p * &q);
+    let phi = Integer::from(&p - 1) * Integer::from(&q - 1);
+    let d = cryptlib::find_inverse(e, &phi);
+    return cryptlib_rsa::RsaPrivateKey::from_factors(&n, e, &d, &p, &q);
+}
+
 fn print_binary(x: &Integer, indent: u32) {
     let mut output = String::new();
     for _ in 0..indent {